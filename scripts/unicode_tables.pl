@@ -0,0 +1,110 @@
+#!/usr/bin/env perl
+# Generates the tables of the Unicode properties from the Unicode Character Database of the
+# version that the chars of Rust follow, char::UNICODE_VERSION, downloaded from
+# https://www.unicode.org/Public/<version>/ucd/:
+#   perl scripts/unicode_tables.pl path/to/ucd > src/matcher/unicode/tables.rs
+use strict;
+use warnings;
+
+my $ucd = shift or die "Usage: $0 UCD_DIRECTORY\n";
+
+my %aliases = read_aliases("$ucd/PropertyValueAliases.txt");
+my ($version, %categories) = read_values("$ucd/extracted/DerivedGeneralCategory.txt", "gc", "Cn");
+my ($scripts_version, %scripts) = read_values("$ucd/Scripts.txt", "sc", "Zzzz");
+die "The files are from Unicode $version and $scripts_version\n" if $version ne $scripts_version;
+my ($major, $minor, $update) = split /\./, $version;
+
+print "// Generated by scripts/unicode_tables.pl from the Unicode $version data, do not edit\n";
+print "// The tables are the sorted ranges of the chars that have a property, and the properties\n";
+print "// have their short name first, then their long name and the other aliases\n\n";
+print "use super::PropertyTable;\n\n";
+print "// The version of the data, that has to be the one of the chars of Rust\n";
+print "#[cfg(test)]\n";
+print "pub const UNICODE_VERSION: (u8, u8, u8) = ($major, $minor, $update);\n\n";
+
+print_properties("GENERAL_CATEGORIES", "gc", \%categories);
+print "\n";
+print_properties("SCRIPTS", "sc", \%scripts);
+
+# The aliases of the values of each property by their short name, with the short names of the
+# values that a general category groups, like "Ll" and "Lu" for "L"
+sub read_aliases {
+    my ($path) = @_;
+    my %aliases;
+    open my $file, "<", $path or die "Can't read $path: $!\n";
+    while (my $line = <$file>) {
+        my ($fields, $comment) = split /#/, $line, 2;
+        my ($property, @names) = map { s/^\s+|\s+$//gr } split /;/, $fields;
+        next if !defined $property || $property !~ /^(gc|sc)$/;
+        my @members = defined $comment ? $comment =~ /\b(\w\w)\b/g : ();
+        $aliases{$property}{$names[0]} = { names => \@names, members => \@members };
+    }
+    return %aliases;
+}
+
+# The version of the data, then the chars of each value of the property by its short name. The
+# chars that the file leaves out have the default value
+sub read_values {
+    my ($path, $property, $default) = @_;
+    my %short_names = map {
+        my $short_name = $_;
+        map { ($_ => $short_name) } @{$aliases{$property}{$short_name}{names}}
+    } keys %{$aliases{$property}};
+    my @values = ($default) x 0x110000;
+    my $version;
+    open my $file, "<", $path or die "Can't read $path: $!\n";
+    while (my $line = <$file>) {
+        ($version) = $line =~ /-(\d+\.\d+\.\d+)\.txt/ if !defined $version;
+        next unless $line =~ /^([0-9A-F]+)(?:\.\.([0-9A-F]+))?\s*;\s*(\w+)/;
+        my ($start, $end, $name) = (hex $1, hex($2 // $1), $3);
+        my $value = $short_names{$name} // die "Unknown $property value $name in $path\n";
+        @values[$start .. $end] = ($value) x ($end - $start + 1);
+    }
+    die "No version in $path\n" if !defined $version;
+    my %chars;
+    push @{$chars{$values[$_]}}, $_ for 0 .. $#values;
+    return ($version, %chars);
+}
+
+sub print_properties {
+    my ($name, $property, $chars) = @_;
+    my %aliases = %{$aliases{$property}};
+    my @values = sort { lc $a cmp lc $b } keys %aliases;
+    my @tables;
+    print "#[rustfmt::skip]\n";
+    print "pub const $name: PropertyTable = &[\n";
+    for my $value (@values) {
+        my @names = @{$aliases{$value}{names}};
+        my @members = @{$aliases{$value}{members}};
+        my @value_chars = sort { $a <=> $b } map { @{$chars->{$_} // []} } $value, @members;
+        # Katakana_Or_Hiragana is only used by the script extensions, so no char has it
+        next if !@value_chars;
+        my $table_name = uc "${property}_$names[1]";
+        my $names = join(", ", map { "\"$_\"" } @names);
+        print "    (&[$names], $table_name),\n";
+        push @tables, [$table_name, ranges(@value_chars)];
+    }
+    print "];\n";
+    for my $table (@tables) {
+        my ($table_name, @ranges) = @$table;
+        print "\n#[rustfmt::skip]\n";
+        print "const $table_name: &[(char, char)] = &[\n";
+        while (my @line = splice(@ranges, 0, 3)) {
+            print "    ", join(" ", map { sprintf("('\\u{%x}', '\\u{%x}'),", @$_) } @line), "\n";
+        }
+        print "];\n";
+    }
+}
+
+# The ranges of the sorted chars, leaving the surrogates out as they aren't chars
+sub ranges {
+    my @ranges;
+    for my $char (grep { $_ < 0xD800 || $_ > 0xDFFF } @_) {
+        if (@ranges && $ranges[-1][1] + 1 == $char) {
+            $ranges[-1][1] = $char;
+        } else {
+            push @ranges, [$char, $char];
+        }
+    }
+    return @ranges;
+}
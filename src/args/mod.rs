@@ -0,0 +1,221 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[allow(clippy::module_inception)]
+mod tests;
+
+pub const USAGE: &str = "Usage: grep [OPTION]... PATTERN [FILE]...";
+
+pub const HELP: &str = "\
+Search for PATTERN in each FILE, or in the standard input when no FILE is given.
+Example: grep -n -E 'hello|world' menu.h main.c
+
+Pattern selection:
+  -E, --extended-regexp     PATTERN is an extended regular expression (the default)
+  -e, --regexp=PATTERN      use PATTERN for matching, can be given several times
+  -i, --ignore-case         ignore the case of the chars, like the (?i) flag
+      --multiline           search each FILE as a whole, so that a match can span lines and
+                            select all of them, with '^' and '$' matching at the lines like
+                            the (?m) flag
+
+Output control:
+  -v, --invert-match        select the non-matching lines
+  -c, --count               print only the count of selected lines per FILE
+  -o, --only-matching       print only the matched parts of the lines
+  -q, --quiet, --silent     print nothing, exit with 0 as soon as a line is selected
+  -n, --line-number         print the line number with the output lines
+  -H, --with-filename       print the file name with the output lines
+  -h, --no-filename         never print the file name with the output lines
+  -r, --recursive           search the files of the directories recursively
+  -a, --text                search a binary file as if it were text
+  -I                        search a binary file as if it had no match
+      --binary-files=TYPE   TYPE is 'binary' to only tell whether a binary file matches (the
+                            default), 'text' like -a or 'without-match' like -I
+      --help                display this help and exit
+
+When FILE is '-' the standard input is read.
+The lines are decoded from UTF-8 when LC_ALL, LC_CTYPE or LANG is a UTF-8 locale, and byte by
+byte otherwise. A file is binary if it has a NUL byte or a line that can't be decoded.
+Exit status is 0 if any line is selected, 1 otherwise and 2 if an error occurred.";
+
+#[derive(Debug, PartialEq, Clone, Error)]
+pub enum ArgsError {
+    #[error("invalid option -- '{0}'")]
+    UnknownShortOption(char),
+    #[error("unrecognized option '--{0}'")]
+    UnknownLongOption(String),
+    #[error("option requires an argument -- '{0}'")]
+    MissingShortOptionArgument(char),
+    #[error("option '--{0}' requires an argument")]
+    MissingLongOptionArgument(String),
+    #[error("option '--{0}' doesn't allow an argument")]
+    UnexpectedLongOptionArgument(String),
+    #[error("invalid argument '{0}' for '--binary-files'")]
+    InvalidBinaryFilesType(String),
+    #[error("no pattern given")]
+    MissingPattern,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Args {
+    Help,
+    Search(Options),
+}
+
+// How the binary files are searched
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum BinaryFiles {
+    // Only whether the file matches is printed
+    #[default]
+    Binary,
+    Text,
+    WithoutMatch,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Options {
+    // The patterns are kept as bytes, and the paths as given, since they can be invalid UTF-8
+    pub patterns: Vec<Vec<u8>>,
+    pub paths: Vec<PathBuf>,
+    pub is_case_insensitive: bool,
+    pub is_multiline: bool,
+    pub is_recursive: bool,
+    pub is_inverted: bool,
+    pub is_count: bool,
+    pub is_only_matching: bool,
+    pub is_quiet: bool,
+    pub with_line_number: bool,
+    // None when the file names are printed only if there are several files
+    pub with_filename: Option<bool>,
+    pub binary_files: BinaryFiles,
+}
+
+// The args are only read as bytes, so that an arg that isn't valid UTF-8 can still be a path or a
+// pattern
+pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args, ArgsError> {
+    let mut options = Options::default();
+    let mut operands = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(os_arg) = args.next() {
+        let arg = os_arg.as_encoded_bytes();
+        let mut next_bytes = || args.next().map(OsString::into_encoded_bytes);
+        // Everything after "--" is an operand, even if it starts with a dash
+        if arg == b"--" {
+            operands.extend(args.by_ref());
+            break;
+        }
+
+        // Long options, with the argument either after "=" or as the next arg
+        if let Some(long_option) = arg.strip_prefix(b"--") {
+            let (name, value) = match long_option.iter().position(|&byte| byte == b'=') {
+                Some(index) => (
+                    &long_option[..index],
+                    Some(long_option[index + 1..].to_vec()),
+                ),
+                None => (long_option, None),
+            };
+            let name = String::from_utf8_lossy(name);
+            if name == "regexp" {
+                let Some(pattern) = value.or_else(&mut next_bytes) else {
+                    return Err(ArgsError::MissingLongOptionArgument(name.into_owned()));
+                };
+                options.patterns.push(pattern);
+                continue;
+            }
+            if name == "binary-files" {
+                let Some(binary_files) = value.or_else(&mut next_bytes) else {
+                    return Err(ArgsError::MissingLongOptionArgument(name.into_owned()));
+                };
+                options.binary_files = match &binary_files[..] {
+                    b"binary" => BinaryFiles::Binary,
+                    b"text" => BinaryFiles::Text,
+                    b"without-match" => BinaryFiles::WithoutMatch,
+                    _ => {
+                        return Err(ArgsError::InvalidBinaryFilesType(
+                            String::from_utf8_lossy(&binary_files).into_owned(),
+                        ))
+                    }
+                };
+                continue;
+            }
+            if value.is_some() {
+                return Err(ArgsError::UnexpectedLongOptionArgument(name.into_owned()));
+            }
+            match &name[..] {
+                "help" => return Ok(Args::Help),
+                "extended-regexp" => {}
+                "ignore-case" => options.is_case_insensitive = true,
+                "multiline" => options.is_multiline = true,
+                "recursive" => options.is_recursive = true,
+                "invert-match" => options.is_inverted = true,
+                "count" => options.is_count = true,
+                "only-matching" => options.is_only_matching = true,
+                "quiet" | "silent" => options.is_quiet = true,
+                "line-number" => options.with_line_number = true,
+                "with-filename" => options.with_filename = Some(true),
+                "no-filename" => options.with_filename = Some(false),
+                "text" => options.binary_files = BinaryFiles::Text,
+                _ => return Err(ArgsError::UnknownLongOption(name.into_owned())),
+            }
+            continue;
+        }
+
+        // Short options, that can be combined in a single arg like "-rn"
+        if arg.len() > 1 && arg[0] == b'-' {
+            for (index, &flag) in arg.iter().enumerate().skip(1) {
+                match flag {
+                    b'e' => {
+                        // The pattern is either the rest of the arg or the next arg
+                        let rest = &arg[index + 1..];
+                        let pattern = if rest.is_empty() {
+                            next_bytes()
+                        } else {
+                            Some(rest.to_vec())
+                        };
+                        let Some(pattern) = pattern else {
+                            return Err(ArgsError::MissingShortOptionArgument('e'));
+                        };
+                        options.patterns.push(pattern);
+                        break;
+                    }
+                    b'E' => {}
+                    b'i' => options.is_case_insensitive = true,
+                    b'r' => options.is_recursive = true,
+                    b'v' => options.is_inverted = true,
+                    b'c' => options.is_count = true,
+                    b'o' => options.is_only_matching = true,
+                    b'q' => options.is_quiet = true,
+                    b'n' => options.with_line_number = true,
+                    b'H' => options.with_filename = Some(true),
+                    b'h' => options.with_filename = Some(false),
+                    b'a' => options.binary_files = BinaryFiles::Text,
+                    b'I' => options.binary_files = BinaryFiles::WithoutMatch,
+                    _ => {
+                        // The flag is the whole char starting with the byte
+                        let flag = String::from_utf8_lossy(&arg[index..]).chars().next();
+                        return Err(ArgsError::UnknownShortOption(
+                            flag.unwrap_or(char::REPLACEMENT_CHARACTER),
+                        ));
+                    }
+                }
+            }
+            continue;
+        }
+
+        operands.push(os_arg);
+    }
+
+    // Without -e the first operand is the pattern
+    let mut operands = operands.into_iter();
+    if options.patterns.is_empty() {
+        let Some(pattern) = operands.next() else {
+            return Err(ArgsError::MissingPattern);
+        };
+        options.patterns.push(pattern.into_encoded_bytes());
+    }
+    options.paths = operands.map(PathBuf::from).collect();
+
+    Ok(Args::Search(options))
+}
@@ -0,0 +1,253 @@
+#[cfg(test)]
+mod tests {
+    use crate::args::{parse_args, Args, ArgsError, BinaryFiles, Options};
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+        parse_args(args.iter().map(OsString::from))
+    }
+
+    fn patterns(patterns: &[&str]) -> Vec<Vec<u8>> {
+        patterns
+            .iter()
+            .map(|pattern| pattern.as_bytes().to_vec())
+            .collect()
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args_with_pattern_operand() {
+        assert_eq!(
+            parse(&["-E", "a+b"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["a+b"]),
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["a+b", "one.txt", "two.txt"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["a+b"]),
+                paths: paths(&["one.txt", "two.txt"]),
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["one.txt", "-E", "-"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["one.txt"]),
+                paths: paths(&["-"]),
+                ..Options::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_args_with_short_options() {
+        assert_eq!(
+            parse(&["-r", "-n", "-E", "log", "dir"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["log"]),
+                paths: paths(&["dir"]),
+                is_recursive: true,
+                with_line_number: true,
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["-irnvcoqH", "log"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["log"]),
+                is_case_insensitive: true,
+                is_recursive: true,
+                is_inverted: true,
+                is_count: true,
+                is_only_matching: true,
+                is_quiet: true,
+                with_line_number: true,
+                with_filename: Some(true),
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["-aI", "log"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["log"]),
+                binary_files: BinaryFiles::WithoutMatch,
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["-Hh", "log"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["log"]),
+                with_filename: Some(false),
+                ..Options::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_args_with_regexp_options() {
+        assert_eq!(
+            parse(&["-e", "one", "-etwo", "-ne", "three", "file"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["one", "two", "three"]),
+                paths: paths(&["file"]),
+                with_line_number: true,
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["--regexp=one", "--regexp", "-two", "file"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["one", "-two"]),
+                paths: paths(&["file"]),
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["-e", "-", "-e", ""]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["-", ""]),
+                ..Options::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_args_with_long_options() {
+        assert_eq!(
+            parse(&[
+                "--extended-regexp",
+                "--ignore-case",
+                "--multiline",
+                "--recursive",
+                "--invert-match",
+                "--count",
+                "--only-matching",
+                "--silent",
+                "--line-number",
+                "--no-filename",
+                "--text",
+                "log"
+            ]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["log"]),
+                is_case_insensitive: true,
+                is_multiline: true,
+                is_recursive: true,
+                is_inverted: true,
+                is_count: true,
+                is_only_matching: true,
+                is_quiet: true,
+                with_line_number: true,
+                with_filename: Some(false),
+                binary_files: BinaryFiles::Text,
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&[
+                "--binary-files=without-match",
+                "--binary-files",
+                "binary",
+                "log"
+            ]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["log"]),
+                ..Options::default()
+            }))
+        );
+        assert_eq!(parse(&["log", "--help"]), Ok(Args::Help));
+    }
+
+    #[test]
+    fn test_parse_args_with_end_of_options() {
+        assert_eq!(
+            parse(&["-n", "--", "-v", "--count"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["-v"]),
+                paths: paths(&["--count"]),
+                with_line_number: true,
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["-e", "log", "--", "-file"]),
+            Ok(Args::Search(Options {
+                patterns: patterns(&["log"]),
+                paths: paths(&["-file"]),
+                ..Options::default()
+            }))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_args_with_invalid_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let args = [
+            &b"-ne\xe9"[..],
+            b"--regexp=\xff",
+            b"--",
+            b"-\xe9.txt",
+            b"n\xff.txt",
+        ];
+        assert_eq!(
+            parse_args(args.map(|arg| OsString::from_vec(arg.to_vec()))),
+            Ok(Args::Search(Options {
+                patterns: vec![b"\xe9".to_vec(), b"\xff".to_vec()],
+                paths: vec![
+                    PathBuf::from(OsString::from_vec(b"-\xe9.txt".to_vec())),
+                    PathBuf::from(OsString::from_vec(b"n\xff.txt".to_vec())),
+                ],
+                with_line_number: true,
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse_args([OsString::from_vec(b"-n\xff".to_vec())]),
+            Err(ArgsError::UnknownShortOption(char::REPLACEMENT_CHARACTER))
+        );
+    }
+
+    #[test]
+    fn test_parse_args_with_errors() {
+        assert_eq!(parse(&[]), Err(ArgsError::MissingPattern));
+        assert_eq!(parse(&["-r", "--"]), Err(ArgsError::MissingPattern));
+        assert_eq!(
+            parse(&["-rx", "log"]),
+            Err(ArgsError::UnknownShortOption('x'))
+        );
+        assert_eq!(
+            parse(&["-né", "log"]),
+            Err(ArgsError::UnknownShortOption('é'))
+        );
+        assert_eq!(
+            parse(&["--colour", "log"]),
+            Err(ArgsError::UnknownLongOption("colour".to_string()))
+        );
+        assert_eq!(
+            parse(&["-e"]),
+            Err(ArgsError::MissingShortOptionArgument('e'))
+        );
+        assert_eq!(
+            parse(&["--regexp"]),
+            Err(ArgsError::MissingLongOptionArgument("regexp".to_string()))
+        );
+        assert_eq!(
+            parse(&["--count=3", "log"]),
+            Err(ArgsError::UnexpectedLongOptionArgument("count".to_string()))
+        );
+        assert_eq!(
+            parse(&["--binary-files=data", "log"]),
+            Err(ArgsError::InvalidBinaryFilesType("data".to_string()))
+        );
+    }
+}
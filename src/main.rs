@@ -1,25 +1,397 @@
-use matcher::match_pattern;
+use args::{parse_args, Args, BinaryFiles, Options, HELP, USAGE};
+use grep_starter_rust::matcher::{Flags, Match, Mode, PatternError, Regex};
+use std::cmp::Reverse;
 use std::env;
-use std::io;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 
-mod matcher;
+mod args;
+#[allow(clippy::module_inception, clippy::bool_assert_comparison)]
+mod tests;
 
-// Usage: echo <input_text> | ./your_grep.sh -E <pattern>
+const STDIN_NAME: &str = "(standard input)";
+
+// Usage: ./your_grep.sh [OPTION]... -E <pattern> [<file>...]
 fn main() {
-    if env::args().nth(1).unwrap() != "-E" {
-        println!("Expected first argument to be '-E'");
-        process::exit(1);
+    let options = match parse_args(env::args_os().skip(1)) {
+        Ok(Args::Search(options)) => options,
+        Ok(Args::Help) => {
+            // Ignore the write error, e.g. when the help is piped into `head`
+            let _ = writeln!(io::stdout(), "{}\n{}", USAGE, HELP);
+            process::exit(0)
+        }
+        Err(error) => {
+            eprintln!("grep: {}", error);
+            eprintln!("{}", USAGE);
+            eprintln!("Try 'grep --help' for more information.");
+            process::exit(2)
+        }
+    };
+
+    let mode = locale_mode();
+    let flags = Flags {
+        is_case_insensitive: options.is_case_insensitive,
+        is_multiline: options.is_multiline,
+        ..Flags::default()
+    };
+    let mut regexes = Vec::new();
+    for pattern in &options.patterns {
+        match Regex::with_flags(pattern, mode, flags) {
+            Ok(regex) => regexes.push(regex),
+            Err(error) => {
+                print_pattern_error(pattern, &error, mode);
+                process::exit(2)
+            }
+        }
+    }
+
+    let paths = options.paths.clone();
+    let is_recursive = options.is_recursive;
+    let mut grep = Grep {
+        regexes,
+        mode,
+        with_filename: options
+            .with_filename
+            .unwrap_or_else(|| is_searching_several_files(&paths, is_recursive)),
+        options,
+        writer: BufWriter::new(io::stdout().lock()),
+        is_any_match: false,
+        is_any_error: false,
+    };
+    if paths.is_empty() && is_recursive {
+        // Searching recursively without operands searches the working directory, like grep does,
+        // with an empty path so that the file names are printed without the "./" prefix
+        grep.search_path(Path::new(""));
+    } else if paths.is_empty() {
+        grep.search_path(Path::new("-"));
+    }
+    for path in &paths {
+        grep.search_path(path);
+    }
+    let result = grep.writer.flush();
+    grep.record(result.map(|_| false), "(standard output)");
+    process::exit(grep.exit_code())
+}
+
+struct Grep<W: Write> {
+    regexes: Vec<Regex>,
+    mode: Mode,
+    options: Options,
+    with_filename: bool,
+    writer: W,
+    is_any_match: bool,
+    is_any_error: bool,
+}
+
+impl<W: Write> Grep<W> {
+    fn search_path(&mut self, path: &Path) {
+        // A quiet search stops at the first selected line
+        if self.is_any_match && self.options.is_quiet {
+            return;
+        }
+
+        if path == Path::new("-") {
+            let result = self.search_reader(io::stdin().lock(), STDIN_NAME);
+            self.record(result, STDIN_NAME);
+            return;
+        }
+
+        let name = path.display().to_string();
+        let is_working_directory = path.as_os_str().is_empty();
+        if !is_working_directory && !path.is_dir() {
+            let result =
+                File::open(path).and_then(|file| self.search_reader(BufReader::new(file), &name));
+            self.record(result, &name);
+            return;
+        }
+
+        if !self.options.is_recursive {
+            eprintln!("grep: {}: Is a directory", name);
+            self.is_any_error = true;
+            return;
+        }
+
+        let directory = if is_working_directory {
+            Path::new(".")
+        } else {
+            path
+        };
+        let entries =
+            fs::read_dir(directory).and_then(|entries| entries.collect::<io::Result<Vec<_>>>());
+        let mut entries = match entries {
+            Ok(entries) => entries,
+            Err(error) => {
+                self.record(Err(error), &name);
+                return;
+            }
+        };
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            // Symbolic links found while traversing are skipped, only the operands are followed
+            if entry
+                .file_type()
+                .is_ok_and(|file_type| file_type.is_symlink())
+            {
+                continue;
+            }
+            self.search_path(&path.join(entry.file_name()));
+        }
+    }
+
+    // Writes the selected lines, returning whether any line was selected
+    fn search_reader(&mut self, mut reader: impl BufRead, name: &str) -> io::Result<bool> {
+        if !self.options.is_multiline {
+            return self.search_lines(reader, name, None);
+        }
+        let mut text = Vec::new();
+        reader.read_to_end(&mut text)?;
+        let text_matches = self.find_text_matches(&text);
+        self.search_lines(&text[..], name, Some(&text_matches))
+    }
+
+    // Writes the selected lines, that the matches of the whole text select in the multiline mode
+    fn search_lines(
+        &mut self,
+        mut reader: impl BufRead,
+        name: &str,
+        text_matches: Option<&TextMatches>,
+    ) -> io::Result<bool> {
+        // Like grep, a file is binary if its first buffer has a NUL byte, or once a line to print
+        // has a NUL byte or can't be decoded
+        let binary_files = self.options.binary_files;
+        let mut is_binary = binary_files != BinaryFiles::Text && reader.fill_buf()?.contains(&0);
+        if is_binary && binary_files == BinaryFiles::WithoutMatch {
+            return Ok(false);
+        }
+
+        let mut selected_lines_count = 0;
+        let mut line = Vec::new();
+        let mut line_number = 0;
+        // The position of the next line in the text
+        let mut position = 0;
+        loop {
+            line.clear();
+            let line_length = reader.read_until(b'\n', &mut line)?;
+            if line_length == 0 {
+                break;
+            }
+            line_number += 1;
+            let line_start = position;
+            position += line_length;
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            let is_match = match text_matches {
+                Some(text_matches) => text_matches.is_line_selected[line_number - 1],
+                None => self.regexes.iter().any(|regex| regex.is_match(&line)),
+            };
+            if is_match == self.options.is_inverted {
+                continue;
+            }
+            selected_lines_count += 1;
+            if self.options.is_quiet {
+                return Ok(true);
+            }
+            if self.options.is_count {
+                continue;
+            }
+
+            is_binary = is_binary || (binary_files != BinaryFiles::Text && self.is_binary(&line));
+            if is_binary && binary_files == BinaryFiles::WithoutMatch {
+                return Ok(false);
+            }
+            if is_binary {
+                // The lines printed so far go out before the message
+                self.writer.flush()?;
+                eprintln!("grep: {}: binary file matches", name);
+                return Ok(true);
+            }
+            if !self.options.is_only_matching {
+                self.write_prefix(name, line_number)?;
+                self.writer.write_all(&line)?;
+                self.writer.write_all(b"\n")?;
+                continue;
+            }
+            // Inverted lines have no matches, so nothing is printed for them
+            if let Some(text_matches) = text_matches {
+                let line_end = line_start + line.len();
+                for found in &text_matches.matches {
+                    if (line_start..=line_end).contains(&found.start) {
+                        self.write_prefix(name, line_number)?;
+                        self.writer.write_all(found.as_bytes(text_matches.text))?;
+                        self.writer.write_all(b"\n")?;
+                    }
+                }
+                continue;
+            }
+            for found in self.find_matches(&line) {
+                self.write_prefix(name, line_number)?;
+                self.writer.write_all(found.as_bytes(&line))?;
+                self.writer.write_all(b"\n")?;
+            }
+        }
+        if self.options.is_count {
+            if self.with_filename {
+                write!(self.writer, "{}:", name)?;
+            }
+            writeln!(self.writer, "{}", selected_lines_count)?;
+        }
+        Ok(selected_lines_count > 0)
+    }
+
+    fn write_prefix(&mut self, name: &str, line_number: usize) -> io::Result<()> {
+        if self.with_filename {
+            write!(self.writer, "{}:", name)?;
+        }
+        if self.options.with_line_number {
+            write!(self.writer, "{}:", line_number)?;
+        }
+        Ok(())
+    }
+
+    fn is_binary(&self, line: &[u8]) -> bool {
+        line.contains(&0) || (self.mode == Mode::Unicode && std::str::from_utf8(line).is_err())
     }
 
-    let pattern = env::args().nth(2).unwrap();
+    // Non-empty matches of all the patterns, leftmost first and without overlaps
+    fn find_matches(&self, line: &[u8]) -> Vec<Match> {
+        let mut matches: Vec<Match> = self
+            .regexes
+            .iter()
+            .flat_map(|regex| regex.find_iter(line))
+            .filter(|found| found.start < found.end)
+            .collect();
+        matches.sort_by_key(|found| (found.start, Reverse(found.end)));
+        let mut end = 0;
+        matches.retain(|found| {
+            let is_kept = found.start >= end;
+            if is_kept {
+                end = found.end;
+            }
+            is_kept
+        });
+        matches
+    }
 
-    let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
+    // Selects the lines that the matches of all the patterns start in or span, including the
+    // empty ones
+    fn find_text_matches<'t>(&self, text: &'t [u8]) -> TextMatches<'t> {
+        // The start of each line, and of the empty one after the last newline
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(
+                text.iter()
+                    .enumerate()
+                    .filter(|(_, &byte)| byte == b'\n')
+                    .map(|(index, _)| index + 1),
+            )
+            .collect();
+        let line_index =
+            |position: usize| line_starts.partition_point(|&start| start <= position) - 1;
+        let mut is_line_selected = vec![false; line_starts.len()];
+        for found in self.regexes.iter().flat_map(|regex| regex.find_iter(text)) {
+            let last_position = found.start.max(found.end.saturating_sub(1));
+            for is_selected in
+                &mut is_line_selected[line_index(found.start)..=line_index(last_position)]
+            {
+                *is_selected = true;
+            }
+        }
+        TextMatches {
+            text,
+            matches: self.find_matches(text),
+            is_line_selected,
+        }
+    }
 
-    if match_pattern(&input_line, &pattern) {
-        process::exit(0)
+    // 0 if any line was selected, 1 otherwise and 2 if an error occurred
+    fn exit_code(&self) -> i32 {
+        // A quiet search succeeds as soon as a line is selected, whatever the errors
+        if self.is_any_match && self.options.is_quiet {
+            0
+        } else if self.is_any_error {
+            2
+        } else if self.is_any_match {
+            0
+        } else {
+            1
+        }
+    }
+
+    // Records the result of a search, warning about an error without stopping the other searches
+    fn record(&mut self, result: io::Result<bool>, name: &str) {
+        match result {
+            Ok(is_match) => self.is_any_match |= is_match,
+            Err(error) => {
+                eprintln!("grep: {}: {}", name, error_message(&error));
+                self.is_any_error = true;
+            }
+        }
+    }
+}
+
+// Like grep, the file names are printed when several files can be searched: with several
+// operands, or a directory searched recursively, which is the working directory without operands
+fn is_searching_several_files(paths: &[PathBuf], is_recursive: bool) -> bool {
+    paths.len() > 1
+        || (is_recursive && (paths.is_empty() || paths.iter().any(|path| path.is_dir())))
+}
+
+// The matches of a whole text in the multiline mode, with the lines they select
+struct TextMatches<'t> {
+    text: &'t [u8],
+    // The non-empty matches, printed with -o
+    matches: Vec<Match>,
+    is_line_selected: Vec<bool>,
+}
+
+// Like grep, the chars are UTF-8 in a UTF-8 locale, and bytes otherwise, as in the C locale that
+// applies when no locale is set
+fn locale_mode() -> Mode {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty());
+    if locale.as_deref().is_some_and(is_utf8_locale) {
+        Mode::Unicode
     } else {
-        process::exit(1)
+        Mode::Bytes
+    }
+}
+
+// Whether the codeset of the locale, e.g. "en_US.UTF-8@euro", is UTF-8, in any case and with or
+// without the dash
+fn is_utf8_locale(locale: &str) -> bool {
+    let Some((_, codeset)) = locale.split_once('.') else {
+        return false;
+    };
+    let codeset = codeset.split('@').next().unwrap_or(codeset);
+    codeset.eq_ignore_ascii_case("UTF-8") || codeset.eq_ignore_ascii_case("UTF8")
+}
+
+// The OS error code is left out, e.g. "No such file or directory (os error 2)"
+fn error_message(error: &io::Error) -> String {
+    let message = error.to_string();
+    match message.find(" (os error") {
+        Some(index) => message[..index].to_string(),
+        None => message,
     }
 }
+
+// Prints the error with the pattern and a caret under the invalid symbol
+fn print_pattern_error(pattern: &[u8], error: &PatternError, mode: Mode) {
+    // The pattern is printed as UTF-8, with the invalid bytes replaced
+    let pattern_string = String::from_utf8_lossy(pattern);
+    let column = match mode {
+        Mode::Unicode => error.column(),
+        // Each byte of the pattern was a char, so the caret goes under the char holding the byte
+        Mode::Bytes => match pattern.get(..error.column()) {
+            Some(bytes) => String::from_utf8_lossy(bytes).chars().count(),
+            None => pattern_string.chars().count() + 1,
+        },
+    };
+    eprintln!("grep: invalid pattern: {}", error);
+    eprintln!("  {}", pattern_string);
+    eprintln!("  {}^", " ".repeat(column - 1));
+}
@@ -0,0 +1,354 @@
+use super::program::{Instruction, Program};
+use super::{case::case_variants, is_matching_char, literal::Literals, Captures, Input, Match};
+use std::collections::HashMap;
+
+#[allow(clippy::module_inception)]
+mod tests;
+
+// Tries the ways the program can match in order of preference, one at a time. Without
+// backreferences each instruction is only taken once at each position, bounding the time by the
+// instructions count times the input length, but the backreferences can make it exponential
+pub fn captures_at<'a>(
+    program: &Program,
+    literals: &Literals,
+    input: Input<'a>,
+    position: usize,
+) -> Option<Captures<'a>> {
+    // A pattern anchored to the start of the string can only be tried at position 0
+    if program.is_anchored && position > 0 {
+        return None;
+    }
+
+    let mut backtracker = Backtracker {
+        program,
+        input,
+        slots: vec![None; program.slots_count],
+        visited_positions: vec![None; program.instructions.len()],
+        visits: Visits::new(program),
+        stack: Vec::new(),
+    };
+    // A match can only start where the prefix does
+    let mut start = literals.find_prefix(input.bytes, position)?;
+    loop {
+        if backtracker.is_matching(0, start, None) {
+            return Some(Captures::from_slots(input.bytes, &backtracker.slots));
+        }
+        if program.is_anchored || start == input.len() {
+            return None;
+        }
+        let next_start = start + input.next_char(start).map_or(1, |(_, length)| length);
+        start = literals.find_prefix(input.bytes, next_start)?;
+    }
+}
+
+enum Frame {
+    // Another way to match, tried once the ones preferred to it failed
+    Explore(usize, usize),
+    // Puts back the slot value from before a save, once the instructions after it failed
+    RestoreSlot(usize, Option<usize>),
+    RestoreVisitedPosition(usize, Option<usize>),
+    // Puts back the slots from before a lookaround that captured groups
+    RestoreSlots(Vec<Option<usize>>),
+}
+
+// Follows the instructions with an explicit stack of the ways left to try, so that long inputs
+// can't overflow the call stack
+struct Backtracker<'p, 'a> {
+    program: &'p Program,
+    input: Input<'a>,
+    slots: Vec<Option<usize>>,
+    // The position each instruction was last reached at by the way being tried. Like in the Pike
+    // VM, an instruction reached again without consuming a char, by a repetition matching no
+    // chars, fails
+    visited_positions: Vec<Option<usize>>,
+    // Replaces the visited positions when there are no backreferences
+    visits: Option<Visits>,
+    stack: Vec<Frame>,
+}
+
+// Without backreferences, whether the instructions match from a position doesn't depend on the
+// way they were reached, so an instruction reached again at a position fails like in the Pike VM:
+// it either failed already or is on the way being tried. A lookbehind matches up to where it's
+// checked, so the instructions of a lookaround are only taken once by each check of it
+struct Visits {
+    // The check that took each instruction at each position last
+    checks: HashMap<(usize, usize), usize>,
+    // The innermost lookaround each instruction is in, or 0 outside of them
+    lookarounds: Vec<usize>,
+    // The current check of each lookaround by its instruction, and of the whole program at 0
+    current_checks: Vec<usize>,
+    checks_count: usize,
+}
+
+impl Visits {
+    fn new(program: &Program) -> Option<Self> {
+        let mut lookarounds = vec![0; program.instructions.len()];
+        for (pc, instruction) in program.instructions.iter().enumerate() {
+            match instruction {
+                Instruction::Backreference { .. } => return None,
+                // The lookarounds inside come later and overwrite their instructions
+                Instruction::Lookahead { end, .. } | Instruction::Lookbehind { end, .. } => {
+                    lookarounds[pc + 1..*end].fill(pc)
+                }
+                _ => {}
+            }
+        }
+        Some(Self {
+            checks: HashMap::new(),
+            lookarounds,
+            current_checks: vec![0; program.instructions.len()],
+            checks_count: 1,
+        })
+    }
+
+    // Whether the current check of the lookaround the instruction is in took it at the position
+    // already, recording that it did
+    fn is_visited(&mut self, pc: usize, position: usize) -> bool {
+        let check = self.current_checks[self.lookarounds[pc]];
+        self.checks.insert((pc, position), check) == Some(check)
+    }
+
+    // Starts a new check of the lookaround, which can take its instructions again
+    fn start_check(&mut self, pc: usize) {
+        self.current_checks[pc] = self.checks_count;
+        self.checks_count += 1;
+    }
+}
+
+impl Backtracker<'_, '_> {
+    // Whether the instructions from `pc` match from the position, up to the end if there is one,
+    // keeping the slots recorded by the first match found
+    fn is_matching(&mut self, pc: usize, position: usize, end: Option<usize>) -> bool {
+        let base = self.stack.len();
+        self.stack.push(Frame::Explore(pc, position));
+        while self.stack.len() > base {
+            match self.stack.pop() {
+                Some(Frame::Explore(pc, position)) => {
+                    if self.is_matching_from(pc, position, end) {
+                        self.unwind_visited_positions(base);
+                        return true;
+                    }
+                }
+                Some(Frame::RestoreSlot(slot, value)) => self.slots[slot] = value,
+                Some(Frame::RestoreVisitedPosition(pc, position)) => {
+                    self.visited_positions[pc] = position
+                }
+                Some(Frame::RestoreSlots(slots)) => self.slots = slots,
+                None => break,
+            }
+        }
+        false
+    }
+
+    // Follows the preferred way from the instruction, leaving the other ways on the stack
+    fn is_matching_from(&mut self, mut pc: usize, mut position: usize, end: Option<usize>) -> bool {
+        let input = self.input;
+        loop {
+            if self.is_visited(pc, position) {
+                return false;
+            }
+
+            let is_continuing = match &self.program.instructions[pc] {
+                Instruction::Char(pattern) => {
+                    match input.next_char(position).filter(|(char, _)| {
+                        char.is_some_and(|char| is_matching_char(pattern, char, input.mode))
+                    }) {
+                        Some((_, char_length)) => {
+                            position += char_length;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Instruction::Split(first, second) => {
+                    self.stack.push(Frame::Explore(*second, position));
+                    pc = *first;
+                    continue;
+                }
+                Instruction::Jump(to) => {
+                    pc = *to;
+                    continue;
+                }
+                Instruction::Save(slot) => {
+                    self.stack
+                        .push(Frame::RestoreSlot(*slot, self.slots[*slot]));
+                    self.slots[*slot] = Some(position);
+                    true
+                }
+                Instruction::StartOfString => position == 0,
+                Instruction::EndOfString => position == input.len(),
+                Instruction::EndOfLastLine => input.is_end_of_last_line(position),
+                Instruction::StartOfLine => input.is_start_of_line(position),
+                Instruction::EndOfLine => input.is_end_of_line(position),
+                Instruction::WordBoundary => input.is_word_boundary(position),
+                Instruction::NonWordBoundary => !input.is_word_boundary(position),
+                Instruction::Backreference {
+                    number,
+                    is_case_insensitive,
+                } => match self.backreference_end(*number, *is_case_insensitive, position) {
+                    Some(backreference_end) => {
+                        position = backreference_end;
+                        true
+                    }
+                    None => false,
+                },
+                Instruction::Lookahead { is_negated, end } => {
+                    self.start_check(pc);
+                    let slots = self.slots.clone();
+                    let is_match = self.is_matching(pc + 1, position, None);
+                    if !self.is_lookaround_passed(is_match, *is_negated, slots) {
+                        return false;
+                    }
+                    pc = *end;
+                    continue;
+                }
+                Instruction::Lookbehind {
+                    is_negated,
+                    min_length,
+                    max_length,
+                    end,
+                } => {
+                    self.start_check(pc);
+                    let slots = self.slots.clone();
+                    let is_match =
+                        self.is_matching_behind(pc + 1, *min_length, *max_length, position);
+                    if !self.is_lookaround_passed(is_match, *is_negated, slots) {
+                        return false;
+                    }
+                    pc = *end;
+                    continue;
+                }
+                Instruction::Match => return end.is_none() || end == Some(position),
+            };
+            if !is_continuing {
+                return false;
+            }
+            pc += 1;
+        }
+    }
+
+    // Whether the instruction was reached at the position already, so that it fails, recording
+    // that it was
+    fn is_visited(&mut self, pc: usize, position: usize) -> bool {
+        if let Some(visits) = &mut self.visits {
+            return visits.is_visited(pc, position);
+        }
+        if self.visited_positions[pc] == Some(position) {
+            return true;
+        }
+        self.stack.push(Frame::RestoreVisitedPosition(
+            pc,
+            self.visited_positions[pc],
+        ));
+        self.visited_positions[pc] = Some(position);
+        false
+    }
+
+    fn start_check(&mut self, pc: usize) {
+        if let Some(visits) = &mut self.visits {
+            visits.start_check(pc);
+        }
+    }
+
+    // The end of the text from the position that matches the one the group captured last
+    fn backreference_end(
+        &self,
+        number: usize,
+        is_case_insensitive: bool,
+        position: usize,
+    ) -> Option<usize> {
+        let (Some(start), Some(end)) = (
+            self.slots.get(2 * number).copied().flatten(),
+            self.slots.get(2 * number + 1).copied().flatten(),
+        ) else {
+            return None;
+        };
+        let group = Match { start, end };
+        if is_case_insensitive {
+            return is_matching_case_insensitive(self.input, group, position);
+        }
+        let bytes = self.input.bytes;
+        bytes[position..]
+            .starts_with(&bytes[group.range()])
+            .then_some(position + group.end - group.start)
+    }
+
+    // Whether the lookbehind instructions from `pc` match up to the position from between
+    // `min_length` and `max_length` chars before it, so that a check only looks as far back as
+    // the lookbehind can match. The closest start is tried first
+    fn is_matching_behind(
+        &mut self,
+        pc: usize,
+        min_length: usize,
+        max_length: usize,
+        position: usize,
+    ) -> bool {
+        let mut start = position;
+        for length in 0..=max_length {
+            if length >= min_length && self.is_matching(pc, start, Some(position)) {
+                return true;
+            }
+            let Some((_, char_length)) = self.input.previous_char(start) else {
+                return false;
+            };
+            start -= char_length;
+        }
+        false
+    }
+
+    // Whether the rest goes on after a lookaround that matched or not, or didn't if it's
+    // negated. A positive lookaround keeps the groups it captured, restored from the slots from
+    // before it when the rest fails
+    fn is_lookaround_passed(
+        &mut self,
+        is_match: bool,
+        is_negated: bool,
+        slots: Vec<Option<usize>>,
+    ) -> bool {
+        if is_match == is_negated {
+            self.slots = slots;
+            return false;
+        }
+        if is_match {
+            self.stack.push(Frame::RestoreSlots(slots));
+        }
+        true
+    }
+
+    // Drops the ways left to try after a match, only putting back the visited positions so that
+    // the instructions can be reached again by another match
+    fn unwind_visited_positions(&mut self, base: usize) {
+        while self.stack.len() > base {
+            if let Some(Frame::RestoreVisitedPosition(pc, position)) = self.stack.pop() {
+                self.visited_positions[pc] = position;
+            }
+        }
+    }
+}
+
+// The end of the text from the position that matches the captured one char by char, ignoring
+// their case
+fn is_matching_case_insensitive(input: Input, group: Match, position: usize) -> Option<usize> {
+    let mut captured_position = group.start;
+    let mut end = position;
+    while captured_position < group.end {
+        let (captured_char, captured_length) = input.next_char(captured_position)?;
+        let (char, length) = input.next_char(end)?;
+        let is_matching = match (captured_char, char) {
+            (Some(captured_char), Some(char)) => {
+                case_variants(char, input.mode).any(|char| char == captured_char)
+            }
+            // The invalid bytes have to be the same
+            _ => {
+                input.bytes[captured_position..captured_position + captured_length]
+                    == input.bytes[end..end + length]
+            }
+        };
+        if !is_matching {
+            return None;
+        }
+        captured_position += captured_length;
+        end += length;
+    }
+    Some(end)
+}
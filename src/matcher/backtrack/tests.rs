@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use crate::matcher::backtrack::captures_at;
+    use crate::matcher::literal::Literals;
+    use crate::matcher::pattern::parse_pattern;
+    use crate::matcher::program::compile;
+    use crate::matcher::{Input, Match, Mode};
+
+    fn input(input_string: &str) -> Input<'_> {
+        Input::new(input_string.as_bytes(), Mode::Unicode)
+    }
+
+    #[test]
+    fn test_captures_at() {
+        let patterns = parse_pattern("(\\w+) and \\1").unwrap();
+        let captures = captures_at(
+            &compile(&patterns).unwrap(),
+            &Literals::new(&patterns, Mode::Unicode),
+            input("dogs and cats and cats"),
+            0,
+        )
+        .unwrap();
+        assert_eq!(captures.as_str(0), Some("cats and cats"));
+        assert_eq!(captures.as_str(1), Some("cats"));
+
+        let patterns = parse_pattern("^a").unwrap();
+        assert_eq!(
+            captures_at(
+                &compile(&patterns).unwrap(),
+                &Literals::new(&patterns, Mode::Unicode),
+                input("aa"),
+                1
+            ),
+            None
+        );
+        let patterns = parse_pattern("a").unwrap();
+        assert_eq!(
+            captures_at(
+                &compile(&patterns).unwrap(),
+                &Literals::new(&patterns, Mode::Unicode),
+                input("aba"),
+                1
+            )
+            .and_then(|captures| captures.get(0)),
+            Some(Match { start: 2, end: 3 })
+        );
+    }
+}
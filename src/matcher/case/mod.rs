@@ -0,0 +1,106 @@
+use super::Mode;
+
+#[allow(clippy::module_inception)]
+mod tests;
+
+// The chars that fold to another char without being its uppercase, e.g. the final sigma, the
+// Kelvin sign or the Greek titlecase letters, with the char they fold to
+const OTHER_CASES: &[(char, char)] = &[
+    ('\u{00b5}', '\u{03bc}'),
+    ('\u{017f}', '\u{0073}'),
+    ('\u{01c5}', '\u{01c6}'),
+    ('\u{01c8}', '\u{01c9}'),
+    ('\u{01cb}', '\u{01cc}'),
+    ('\u{01f2}', '\u{01f3}'),
+    ('\u{0345}', '\u{03b9}'),
+    ('\u{03c2}', '\u{03c3}'),
+    ('\u{03d0}', '\u{03b2}'),
+    ('\u{03d1}', '\u{03b8}'),
+    ('\u{03d5}', '\u{03c6}'),
+    ('\u{03d6}', '\u{03c0}'),
+    ('\u{03f0}', '\u{03ba}'),
+    ('\u{03f1}', '\u{03c1}'),
+    ('\u{03f4}', '\u{03b8}'),
+    ('\u{03f5}', '\u{03b5}'),
+    ('\u{1c80}', '\u{0432}'),
+    ('\u{1c81}', '\u{0434}'),
+    ('\u{1c82}', '\u{043e}'),
+    ('\u{1c83}', '\u{0441}'),
+    ('\u{1c84}', '\u{0442}'),
+    ('\u{1c85}', '\u{0442}'),
+    ('\u{1c86}', '\u{044a}'),
+    ('\u{1c87}', '\u{0463}'),
+    ('\u{1c88}', '\u{a64b}'),
+    ('\u{1e9b}', '\u{1e61}'),
+    ('\u{1e9e}', '\u{00df}'),
+    ('\u{1f88}', '\u{1f80}'),
+    ('\u{1f89}', '\u{1f81}'),
+    ('\u{1f8a}', '\u{1f82}'),
+    ('\u{1f8b}', '\u{1f83}'),
+    ('\u{1f8c}', '\u{1f84}'),
+    ('\u{1f8d}', '\u{1f85}'),
+    ('\u{1f8e}', '\u{1f86}'),
+    ('\u{1f8f}', '\u{1f87}'),
+    ('\u{1f98}', '\u{1f90}'),
+    ('\u{1f99}', '\u{1f91}'),
+    ('\u{1f9a}', '\u{1f92}'),
+    ('\u{1f9b}', '\u{1f93}'),
+    ('\u{1f9c}', '\u{1f94}'),
+    ('\u{1f9d}', '\u{1f95}'),
+    ('\u{1f9e}', '\u{1f96}'),
+    ('\u{1f9f}', '\u{1f97}'),
+    ('\u{1fa8}', '\u{1fa0}'),
+    ('\u{1fa9}', '\u{1fa1}'),
+    ('\u{1faa}', '\u{1fa2}'),
+    ('\u{1fab}', '\u{1fa3}'),
+    ('\u{1fac}', '\u{1fa4}'),
+    ('\u{1fad}', '\u{1fa5}'),
+    ('\u{1fae}', '\u{1fa6}'),
+    ('\u{1faf}', '\u{1fa7}'),
+    ('\u{1fbc}', '\u{1fb3}'),
+    ('\u{1fbe}', '\u{03b9}'),
+    ('\u{1fcc}', '\u{1fc3}'),
+    ('\u{1ffc}', '\u{1ff3}'),
+    ('\u{2126}', '\u{03c9}'),
+    ('\u{212a}', '\u{006b}'),
+    ('\u{212b}', '\u{00e5}'),
+];
+
+// The simple case folding of the char, which the chars of different cases fold to, like the
+// uppercase and the lowercase sigmas and the final one. Most chars fold to the lowercase of their
+// uppercase, but the Turkish dotless i doesn't fold to "i", and the dotted capital I doesn't fold
+// to a single char so it stays itself
+pub fn fold(char: char) -> char {
+    if char == '\u{131}' {
+        return char;
+    }
+    let upper = single_char(char.to_uppercase()).unwrap_or(char);
+    single_char(upper.to_lowercase()).unwrap_or(upper)
+}
+
+// The chars that fold like the char, including itself. In the bytes mode the chars are bytes, so
+// only the ASCII letters have other cases
+pub fn case_variants(char: char, mode: Mode) -> impl Iterator<Item = char> {
+    let (folded, upper) = match mode {
+        Mode::Unicode => {
+            let folded = fold(char);
+            let upper = single_char(folded.to_uppercase())
+                .filter(|&upper| fold(upper) == folded)
+                .unwrap_or(folded);
+            (folded, upper)
+        }
+        Mode::Bytes => (char.to_ascii_lowercase(), char.to_ascii_uppercase()),
+    };
+    let other_cases = OTHER_CASES
+        .iter()
+        .filter(move |(_, c)| mode == Mode::Unicode && *c == folded)
+        .map(|(char, _)| *char);
+    std::iter::once(folded)
+        .chain(Some(upper).filter(|&upper| upper != folded))
+        .chain(other_cases)
+}
+
+fn single_char(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    let char = chars.next()?;
+    chars.next().is_none().then_some(char)
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::matcher::case::{case_variants, fold};
+    use crate::matcher::Mode;
+
+    fn sorted_variants(char: char, mode: Mode) -> Vec<char> {
+        let mut variants: Vec<char> = case_variants(char, mode).collect();
+        variants.sort_unstable();
+        variants
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold('A'), 'a');
+        assert_eq!(fold('a'), 'a');
+        assert_eq!(fold('1'), '1');
+        assert_eq!(fold('Ä'), 'ä');
+        assert_eq!(fold('ẞ'), 'ß');
+        assert_eq!(fold('ß'), 'ß');
+        assert_eq!(fold('Σ'), 'σ');
+        assert_eq!(fold('ς'), 'σ');
+        assert_eq!(fold('ǅ'), 'ǆ');
+        assert_eq!(fold('\u{212a}'), 'k');
+        // The Turkish dotted and dotless i only fold to themselves
+        assert_eq!(fold('İ'), 'İ');
+        assert_eq!(fold('ı'), 'ı');
+    }
+
+    #[test]
+    fn test_case_variants() {
+        assert_eq!(sorted_variants('a', Mode::Unicode), vec!['A', 'a']);
+        assert_eq!(
+            sorted_variants('k', Mode::Unicode),
+            vec!['K', 'k', '\u{212a}']
+        );
+        assert_eq!(sorted_variants('ß', Mode::Unicode), vec!['ß', 'ẞ']);
+        assert_eq!(sorted_variants('ς', Mode::Unicode), vec!['Σ', 'ς', 'σ']);
+        assert_eq!(sorted_variants('ǅ', Mode::Unicode), vec!['Ǆ', 'ǅ', 'ǆ']);
+        assert_eq!(sorted_variants('i', Mode::Unicode), vec!['I', 'i']);
+        assert_eq!(sorted_variants('ı', Mode::Unicode), vec!['ı']);
+        assert_eq!(sorted_variants('İ', Mode::Unicode), vec!['İ']);
+        assert_eq!(sorted_variants('-', Mode::Unicode), vec!['-']);
+        // The bytes aren't Unicode chars
+        assert_eq!(sorted_variants('k', Mode::Bytes), vec!['K', 'k']);
+        assert_eq!(sorted_variants('Ä', Mode::Bytes), vec!['Ä']);
+    }
+}
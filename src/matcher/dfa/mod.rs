@@ -0,0 +1,363 @@
+use super::program::{Instruction, Program};
+use super::{is_matching_char, is_word_char, Input, Mode};
+use std::collections::HashMap;
+
+#[allow(clippy::module_inception, clippy::bool_assert_comparison)]
+mod tests;
+
+// The states are built while matching and kept for the next inputs, until there are too many of
+// them and the cache starts over
+const MAX_STATES_COUNT: usize = 4096;
+
+// Transitions on ASCII chars are looked up in an array, the other ones in a map
+const ASCII_CHARS_COUNT: usize = 128;
+
+// A DFA state is the set of NFA threads at a position, only keeping the instructions that consume
+// a char, the assertions that are checked once the next char or the end of the input is known,
+// and the match
+struct State {
+    pcs: Vec<usize>,
+    // The threads that went on from the end of the last line before a newline, so they only
+    // match if the input ends after it
+    end_pcs: Vec<usize>,
+    look: Look,
+    is_match: bool,
+    ascii_transitions: [Option<usize>; ASCII_CHARS_COUNT],
+    // The transitions on non-ASCII chars, and on the bytes that aren't valid UTF-8
+    transitions: HashMap<Option<char>, usize>,
+}
+
+// What the assertions know about the position of a state before seeing the next char
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+struct Look {
+    is_start: bool,
+    is_after_word: bool,
+    is_after_newline: bool,
+}
+
+#[derive(Default)]
+pub struct Cache {
+    states: Vec<State>,
+    state_ids: HashMap<(Vec<usize>, Vec<usize>, Look), usize>,
+    // The start states at the start of the string, after a word char, after a newline and after
+    // another char
+    start_state_ids: [Option<usize>; 4],
+}
+
+// Whether the program matches from the position, stopping at the first match found instead of
+// looking for the preferred one, which needs the Pike VM
+pub fn is_match_at(program: &Program, cache: &mut Cache, input: Input, position: usize) -> bool {
+    let previous_char = input.previous_char(position).and_then(|(c, _)| c);
+    let look = Look {
+        is_start: position == 0,
+        is_after_word: is_word_char(previous_char, input.mode),
+        is_after_newline: previous_char == Some('\n'),
+    };
+    let mut state_id = cache.start_state(program, look);
+    let mut at = position;
+    while let Some((char, char_length)) = input.next_char(at) {
+        let state = &cache.states[state_id];
+        if state.is_match {
+            return true;
+        }
+        // No thread is left and an anchored program doesn't start new ones
+        if state.pcs.is_empty() && program.is_anchored {
+            return false;
+        }
+        state_id = cache.next_state(program, state_id, char, input.mode);
+        at += char_length;
+    }
+
+    let state = &cache.states[state_id];
+    state.is_match || is_matching_end(program, state)
+}
+
+impl Cache {
+    fn start_state(&mut self, program: &Program, look: Look) -> usize {
+        let index = match look {
+            Look { is_start: true, .. } => 0,
+            Look {
+                is_after_word: true,
+                ..
+            } => 1,
+            Look {
+                is_after_newline: true,
+                ..
+            } => 2,
+            _ => 3,
+        };
+        if let Some(state_id) = self.start_state_ids[index] {
+            return state_id;
+        }
+        let mut closure = Closure::new(program, look, None);
+        closure.add(0);
+        let state_id = self.add_state(program, (closure.pcs, Vec::new(), look));
+        self.start_state_ids[index] = Some(state_id);
+        state_id
+    }
+
+    fn next_state(
+        &mut self,
+        program: &Program,
+        state_id: usize,
+        char: Option<char>,
+        mode: Mode,
+    ) -> usize {
+        let state = &self.states[state_id];
+        let cached_state_id = match ascii_index(char) {
+            Some(index) => state.ascii_transitions[index],
+            None => state.transitions.get(&char).copied(),
+        };
+        if let Some(next_state_id) = cached_state_id {
+            return next_state_id;
+        }
+
+        // The assertions waiting for the next char are checked before it's consumed
+        let is_before_word = is_word_char(char, mode);
+        let is_before_newline = char == Some('\n');
+        let mut look_closure = Closure::new(
+            program,
+            state.look,
+            Some(NextChar::Char {
+                is_word: is_before_word,
+                is_newline: is_before_newline,
+            }),
+        );
+        for &pc in &state.pcs {
+            look_closure.add(pc);
+        }
+        let look = Look {
+            is_start: false,
+            is_after_word: is_before_word,
+            is_after_newline: is_before_newline,
+        };
+        let mut closure = Closure::new(program, look, None);
+        for &pc in &look_closure.pcs {
+            match &program.instructions[pc] {
+                Instruction::Char(pattern)
+                    if char.is_some_and(|char| is_matching_char(pattern, char, mode)) =>
+                {
+                    closure.add(pc + 1)
+                }
+                // A match found once the assertions are checked is kept by the next state
+                Instruction::Match => closure.add(pc),
+                _ => {}
+            }
+        }
+        // Unless it's anchored, a match can start after any char
+        if !program.is_anchored {
+            closure.add(0);
+        }
+        closure.pcs.sort_unstable();
+        let end_pcs = last_line_end_pcs(program, state.look, &look_closure.last_line_pcs, mode);
+
+        let key = (closure.pcs, end_pcs, look);
+        if self.states.len() == MAX_STATES_COUNT && !self.state_ids.contains_key(&key) {
+            self.clear();
+            return self.add_state(program, key);
+        }
+        let next_state_id = self.add_state(program, key);
+        let state = &mut self.states[state_id];
+        match ascii_index(char) {
+            Some(index) => state.ascii_transitions[index] = Some(next_state_id),
+            None => {
+                state.transitions.insert(char, next_state_id);
+            }
+        }
+        next_state_id
+    }
+
+    // Adds the state unless it's already there, returning its id
+    fn add_state(&mut self, program: &Program, key: (Vec<usize>, Vec<usize>, Look)) -> usize {
+        if let Some(&state_id) = self.state_ids.get(&key) {
+            return state_id;
+        }
+        let state_id = self.states.len();
+        self.state_ids.insert(key.clone(), state_id);
+        let (pcs, end_pcs, look) = key;
+        self.states.push(State {
+            is_match: pcs
+                .iter()
+                .any(|&pc| matches!(program.instructions[pc], Instruction::Match)),
+            pcs,
+            end_pcs,
+            look,
+            ascii_transitions: [None; ASCII_CHARS_COUNT],
+            transitions: HashMap::new(),
+        });
+        state_id
+    }
+
+    fn clear(&mut self) {
+        self.states.clear();
+        self.state_ids.clear();
+        self.start_state_ids = [None; 4];
+    }
+}
+
+fn ascii_index(char: Option<char>) -> Option<usize> {
+    char.filter(char::is_ascii).map(|char| char as usize)
+}
+
+// The threads after the newline of the ones that went on from the end of the last line before
+// it, as if the newline was the last char
+fn last_line_end_pcs(
+    program: &Program,
+    look: Look,
+    last_line_pcs: &[usize],
+    mode: Mode,
+) -> Vec<usize> {
+    if last_line_pcs.is_empty() {
+        return Vec::new();
+    }
+    let mut last_line_closure = Closure::new(
+        program,
+        look,
+        Some(NextChar::Char {
+            is_word: false,
+            is_newline: true,
+        }),
+    );
+    last_line_closure.is_newline_last = true;
+    for &pc in last_line_pcs {
+        last_line_closure.add(pc);
+    }
+    let newline_look = Look {
+        is_start: false,
+        is_after_word: false,
+        is_after_newline: true,
+    };
+    let mut closure = Closure::new(program, newline_look, None);
+    for &pc in &last_line_closure.pcs {
+        match &program.instructions[pc] {
+            Instruction::Char(pattern) if is_matching_char(pattern, '\n', mode) => {
+                closure.add(pc + 1)
+            }
+            Instruction::Match => closure.add(pc),
+            _ => {}
+        }
+    }
+    closure.pcs.sort_unstable();
+    closure.pcs
+}
+
+// Whether the threads waiting for the end of the string reach the match once it's over
+fn is_matching_end(program: &Program, state: &State) -> bool {
+    let mut closure = Closure::new(program, state.look, Some(NextChar::End));
+    for &pc in state.pcs.iter().chain(&state.end_pcs) {
+        closure.add(pc);
+    }
+    closure
+        .pcs
+        .iter()
+        .any(|&pc| matches!(program.instructions[pc], Instruction::Match))
+}
+
+// What follows the position, once it's known
+#[derive(Clone, Copy)]
+enum NextChar {
+    Char { is_word: bool, is_newline: bool },
+    End,
+}
+
+// Follows the instructions that don't consume a char, collecting the ones that end the threads
+// and the assertions that wait for the next char
+struct Closure<'p> {
+    program: &'p Program,
+    look: Look,
+    next_char: Option<NextChar>,
+    pcs: Vec<usize>,
+    // The instructions after the ends of the last line before a newline, unless the newline is
+    // known to be the last char
+    last_line_pcs: Vec<usize>,
+    is_newline_last: bool,
+    is_visited: Vec<bool>,
+    stack: Vec<usize>,
+}
+
+impl<'p> Closure<'p> {
+    fn new(program: &'p Program, look: Look, next_char: Option<NextChar>) -> Self {
+        Closure {
+            program,
+            look,
+            next_char,
+            pcs: Vec::new(),
+            last_line_pcs: Vec::new(),
+            is_newline_last: false,
+            is_visited: vec![false; program.instructions.len()],
+            stack: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, pc: usize) {
+        self.stack.push(pc);
+        while let Some(pc) = self.stack.pop() {
+            if self.is_visited[pc] {
+                continue;
+            }
+            self.is_visited[pc] = true;
+            match &self.program.instructions[pc] {
+                Instruction::Split(first, second) => {
+                    self.stack.push(*second);
+                    self.stack.push(*first);
+                }
+                Instruction::Jump(to) => self.stack.push(*to),
+                Instruction::Save(_) => self.stack.push(pc + 1),
+                Instruction::StartOfString => {
+                    if self.look.is_start {
+                        self.stack.push(pc + 1);
+                    }
+                }
+                Instruction::EndOfString => match self.next_char {
+                    Some(NextChar::End) => self.stack.push(pc + 1),
+                    Some(NextChar::Char { .. }) => {}
+                    None => self.pcs.push(pc),
+                },
+                Instruction::EndOfLastLine => match self.next_char {
+                    Some(NextChar::End) => self.stack.push(pc + 1),
+                    Some(NextChar::Char {
+                        is_newline: true, ..
+                    }) if self.is_newline_last => self.stack.push(pc + 1),
+                    Some(NextChar::Char {
+                        is_newline: true, ..
+                    }) => self.last_line_pcs.push(pc + 1),
+                    Some(NextChar::Char { .. }) => {}
+                    None => self.pcs.push(pc),
+                },
+                Instruction::StartOfLine => {
+                    if self.look.is_start || self.look.is_after_newline {
+                        self.stack.push(pc + 1);
+                    }
+                }
+                Instruction::EndOfLine => match self.next_char {
+                    Some(
+                        NextChar::End
+                        | NextChar::Char {
+                            is_newline: true, ..
+                        },
+                    ) => self.stack.push(pc + 1),
+                    Some(NextChar::Char { .. }) => {}
+                    None => self.pcs.push(pc),
+                },
+                Instruction::WordBoundary | Instruction::NonWordBoundary => {
+                    let Some(next_char) = self.next_char else {
+                        self.pcs.push(pc);
+                        continue;
+                    };
+                    let is_before_word = matches!(next_char, NextChar::Char { is_word: true, .. });
+                    let is_word_boundary = self.look.is_after_word != is_before_word;
+                    let is_negated =
+                        matches!(self.program.instructions[pc], Instruction::NonWordBoundary);
+                    if is_word_boundary != is_negated {
+                        self.stack.push(pc + 1);
+                    }
+                }
+                Instruction::Char(_) | Instruction::Match => self.pcs.push(pc),
+                // The programs with these are only run by the backtracker
+                Instruction::Backreference { .. }
+                | Instruction::Lookahead { .. }
+                | Instruction::Lookbehind { .. } => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod tests {
+    use crate::matcher::dfa::{is_match_at, Cache};
+    use crate::matcher::literal::Literals;
+    use crate::matcher::pattern::parse_pattern;
+    use crate::matcher::pikevm;
+    use crate::matcher::program::compile;
+    use crate::matcher::{Input, Mode};
+
+    #[test]
+    fn test_is_match_at_like_pikevm() {
+        let cases = [
+            ("a+ab", "aaab"),
+            ("\\w+s", "dogs and cats"),
+            ("(a|ab)(c|bcd)(d*)", "abcd"),
+            ("^(ca|cat)s?$", "cats"),
+            ("^(ca|cat)s?$", "cat"),
+            ("^ca$", "cat"),
+            ("ts$", "cats"),
+            ("^$", ""),
+            ("^$", "a"),
+            ("a?$", ""),
+            ("ü+(ß)", "aüüß"),
+            ("\\b\\w+\\b", "a cat-like dog"),
+            ("\\Bat\\b", "bat at"),
+            ("a\\b", "ab a"),
+            ("(\\b|x)+y", "xy y"),
+            ("[^abc]{2}", "abcda"),
+            ("x{2,}", "xaxx"),
+            ("a.c", "a\u{fffd}c"),
+            ("(?m)^$", "a\n\nb"),
+            ("(?m)^b|a$", "a\nb\nab"),
+            ("(?m)^\\w+$", "a b\ncd\n"),
+            ("(?is)a.B", "A\nb"),
+            ("(^a|b$)", "ab"),
+            ("a\\Z", "a\n"),
+            ("a\\Z", "a\nb"),
+            ("a\\Z", "a\n\n"),
+            ("a\\Z\n", "a\n"),
+            ("a\\Z\\s*$", "a\n"),
+            ("a\\Z$", "a\n"),
+            ("a\\Z\\Z", "a\n"),
+            ("a\\Z\n\\Z", "a\n"),
+            ("a\\Z(?m)$\n", "a\n"),
+            ("\\Z", ""),
+            ("(^a|b$)", "ba"),
+            ("^(foo|bar)$", "bar"),
+            ("x*^a", "a"),
+        ];
+        for (pattern_string, input_string) in cases {
+            let patterns = parse_pattern(pattern_string).unwrap();
+            let program = compile(&patterns).unwrap();
+            let literals = Literals::new(&patterns, Mode::Unicode);
+            let mut cache = Cache::default();
+            for position in 0..=input_string.len() {
+                if !input_string.is_char_boundary(position) {
+                    continue;
+                }
+                let input = Input::new(input_string.as_bytes(), Mode::Unicode);
+                assert_eq!(
+                    is_match_at(&program, &mut cache, input, position),
+                    pikevm::captures_at(
+                        &program,
+                        program.slots_count,
+                        &mut pikevm::Cache::default(),
+                        &literals,
+                        input,
+                        position
+                    )
+                    .is_some(),
+                    "{} on {:?} from {}",
+                    pattern_string,
+                    input_string,
+                    position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_match_at_invalid_utf8() {
+        let program = compile(&parse_pattern("^(a.c|a[^x]d|..)$").unwrap()).unwrap();
+        let mut cache = Cache::default();
+        let input = |bytes| Input::new(bytes, Mode::Unicode);
+        assert_eq!(
+            is_match_at(&program, &mut cache, input(b"a\xffc"), 0),
+            false
+        );
+        assert_eq!(
+            is_match_at(&program, &mut cache, input(b"a\xffd"), 0),
+            false
+        );
+        assert_eq!(
+            is_match_at(&program, &mut cache, input(b"\xc3\xa9\xc3"), 0),
+            false
+        );
+        assert_eq!(
+            is_match_at(&program, &mut cache, input(b"\xc3\xa9b"), 0),
+            true
+        );
+        // The transitions depend on the mode, so it takes another cache
+        let mut cache = Cache::default();
+        let input = |bytes| Input::new(bytes, Mode::Bytes);
+        assert_eq!(is_match_at(&program, &mut cache, input(b"a\xffc"), 0), true);
+        assert_eq!(
+            is_match_at(&program, &mut cache, input(b"\xc3\xa9"), 0),
+            true
+        );
+    }
+
+    #[test]
+    fn test_is_match_at_with_full_cache() {
+        // The 13th char from the end being an "a" takes a state for each of the last 13 chars
+        let program = compile(&parse_pattern("^[ab]*a[ab]{12}$").unwrap()).unwrap();
+        let mut cache = Cache::default();
+        let mut seed = 1u32;
+        let input_string: String = (0..20_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                if seed >> 16 & 1 == 0 {
+                    'a'
+                } else {
+                    'b'
+                }
+            })
+            .collect();
+        for end in (13..input_string.len()).step_by(997) {
+            let input_string = &input_string[..end];
+            assert_eq!(
+                is_match_at(
+                    &program,
+                    &mut cache,
+                    Input::new(input_string.as_bytes(), Mode::Unicode),
+                    0
+                ),
+                input_string.as_bytes()[end - 13] == b'a'
+            );
+        }
+    }
+}
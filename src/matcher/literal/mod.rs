@@ -0,0 +1,146 @@
+use super::{pattern::Pattern, Mode};
+
+#[allow(clippy::module_inception)]
+mod tests;
+
+// The literals are cut to this many chars, as a repetition can make them as long as its count and
+// a part of them is enough to skip the inputs
+const MAX_LITERAL_LENGTH: usize = 256;
+
+// Literal strings that every match contains, to skip the inputs and positions that can't match
+// before running an engine
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Literals {
+    // Every match starts with the prefix
+    pub prefix: Vec<u8>,
+    // Every match ends with the suffix, which also ends the input if the pattern ends with "$"
+    pub suffix: Vec<u8>,
+    pub is_suffix_at_end: bool,
+}
+
+impl Literals {
+    pub fn new(patterns: &[Pattern], mode: Mode) -> Self {
+        let mut prefix = Vec::new();
+        add_literal_chars(patterns, false, &mut prefix);
+        let mut suffix = Vec::new();
+        add_literal_chars(patterns, true, &mut suffix);
+        // A char beyond the Latin-1 chars can't be in the input in the bytes mode
+        for chars in [&mut prefix, &mut suffix] {
+            let encodable_length = chars
+                .iter()
+                .take_while(|char| mode == Mode::Unicode || u8::try_from(**char).is_ok())
+                .count();
+            chars.truncate(encodable_length);
+        }
+        // The suffix chars were added from the last one
+        suffix.reverse();
+        Literals {
+            prefix: encode(&prefix, mode),
+            suffix: encode(&suffix, mode),
+            is_suffix_at_end: matches!(patterns.last(), Some(Pattern::EndOfString)),
+        }
+    }
+
+    // The first position from `position` where a match can start, if the input can match at all
+    pub fn find_candidate(&self, input: &[u8], position: usize) -> Option<usize> {
+        let haystack = &input[position..];
+        let is_suffix_found = if self.is_suffix_at_end {
+            haystack.ends_with(&self.suffix)
+        } else {
+            find_bytes(haystack, &self.suffix).is_some()
+        };
+        if !is_suffix_found {
+            return None;
+        }
+        self.find_prefix(input, position)
+    }
+
+    // The first position from `position` where the prefix starts
+    pub fn find_prefix(&self, input: &[u8], position: usize) -> Option<usize> {
+        find_bytes(&input[position..], &self.prefix).map(|index| position + index)
+    }
+}
+
+// Encodes the chars as the input contains them
+fn encode(chars: &[char], mode: Mode) -> Vec<u8> {
+    match mode {
+        Mode::Unicode => chars.iter().collect::<String>().into_bytes(),
+        Mode::Bytes => chars.iter().map(|&char| char as u8).collect(),
+    }
+}
+
+// The first position of the needle in the haystack, found by looking for its first byte and
+// checking the rest only there
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let Some(&first_byte) = needle.first() else {
+        return Some(0);
+    };
+    let mut start = 0;
+    while let Some(index) = haystack[start..]
+        .iter()
+        .position(|&byte| byte == first_byte)
+    {
+        let position = start + index;
+        if haystack[position..].starts_with(needle) {
+            return Some(position);
+        }
+        start = position + 1;
+    }
+    None
+}
+
+// Adds the chars that every match of the patterns starts with, or ends with from the last one if
+// it's reversed, returning whether the patterns only match these chars so the ones next to them
+// can be added too
+fn add_literal_chars(patterns: &[Pattern], is_reversed: bool, chars: &mut Vec<char>) -> bool {
+    let add_literal_chars_of = |pattern: &Pattern| match pattern {
+        Pattern::Literal(_) if chars.len() == MAX_LITERAL_LENGTH => false,
+        Pattern::Literal(c) => {
+            chars.push(*c);
+            true
+        }
+        // The assertions are zero-width, so the chars around them are next to each other
+        Pattern::StartOfString
+        | Pattern::EndOfString
+        | Pattern::EndOfLastLine
+        | Pattern::StartOfLine
+        | Pattern::EndOfLine
+        | Pattern::WordBoundary
+        | Pattern::NonWordBoundary
+        | Pattern::Lookahead { .. }
+        | Pattern::Lookbehind { .. } => true,
+        Pattern::CapturingGroup(group)
+        | Pattern::NamedCapturingGroup(_, group)
+        | Pattern::NonCapturingGroup(group) => add_literal_chars(group, is_reversed, chars),
+        Pattern::Repeat {
+            pattern, min, max, ..
+        } if *min > 0 => {
+            let mut repeated_chars = Vec::new();
+            let is_literal = add_literal_chars(
+                std::slice::from_ref(pattern),
+                is_reversed,
+                &mut repeated_chars,
+            );
+            let remaining_length = MAX_LITERAL_LENGTH - chars.len();
+            if !is_literal {
+                chars.extend(repeated_chars.into_iter().take(remaining_length));
+                return false;
+            }
+            if repeated_chars.len().saturating_mul(*min) > remaining_length {
+                chars.extend(repeated_chars.iter().cycle().take(remaining_length));
+                return false;
+            }
+            for _ in 0..*min {
+                chars.extend_from_slice(&repeated_chars);
+            }
+            *max == Some(*min)
+        }
+        _ => false,
+    };
+
+    if is_reversed {
+        patterns.iter().rev().all(add_literal_chars_of)
+    } else {
+        patterns.iter().all(add_literal_chars_of)
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+    use crate::matcher::literal::Literals;
+    use crate::matcher::pattern::parse_pattern;
+    use crate::matcher::Mode;
+
+    fn literals(pattern_string: &str) -> Literals {
+        Literals::new(&parse_pattern(pattern_string).unwrap(), Mode::Unicode)
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            literals("ERROR \\d+ in (main)"),
+            Literals {
+                prefix: b"ERROR ".to_vec(),
+                suffix: b" in main".to_vec(),
+                is_suffix_at_end: false,
+            }
+        );
+        assert_eq!(
+            literals("^abc$"),
+            Literals {
+                prefix: b"abc".to_vec(),
+                suffix: b"abc".to_vec(),
+                is_suffix_at_end: true,
+            }
+        );
+        assert_eq!(
+            literals("(ab){2}c+d?"),
+            Literals {
+                prefix: b"ababc".to_vec(),
+                suffix: Vec::new(),
+                is_suffix_at_end: false,
+            }
+        );
+        assert_eq!(
+            literals("x?(a|b)(cd+)+ef{1,2}\\w"),
+            Literals {
+                prefix: Vec::new(),
+                suffix: Vec::new(),
+                is_suffix_at_end: false,
+            }
+        );
+        assert_eq!(literals("(cd+)+ef{1,2}").prefix, b"cd");
+        assert_eq!(literals("(cd+)+ef{1,2}").suffix, b"f");
+        assert_eq!(literals("ü+ß$").prefix, "ü".as_bytes());
+        assert_eq!(literals("ü+ß$").suffix, "üß".as_bytes());
+        // The literals of long repetitions are cut
+        assert_eq!(literals("a{1000000000}").prefix, vec![b'a'; 256]);
+        let long_literals = literals("x(ab){1000}y");
+        assert_eq!(long_literals.prefix.len(), 256);
+        assert!(long_literals.prefix.starts_with(b"xabab"));
+        assert_eq!(long_literals.suffix.len(), 256);
+        assert!(long_literals.suffix.ends_with(b"ababy"));
+        assert_eq!(literals(&"a".repeat(300)).prefix, vec![b'a'; 256]);
+    }
+
+    #[test]
+    fn test_literals_bytes() {
+        let literals =
+            |pattern_string| Literals::new(&parse_pattern(pattern_string).unwrap(), Mode::Bytes);
+        assert_eq!(
+            literals("\u{e9}t\u{e9}"),
+            Literals {
+                prefix: b"\xe9t\xe9".to_vec(),
+                suffix: b"\xe9t\xe9".to_vec(),
+                is_suffix_at_end: false,
+            }
+        );
+        // The chars beyond Latin-1 can't be in the input, which is matched byte by byte
+        assert_eq!(literals("ab\u{20ac}cd").prefix, b"ab");
+        assert_eq!(literals("ab\u{20ac}cd").suffix, b"cd");
+    }
+
+    #[test]
+    fn test_find_candidate() {
+        let literals = literals("ERROR \\d+ in (main)");
+        assert_eq!(
+            literals.find_candidate(b"INFO 1 in main, ERROR 2 in main", 0),
+            Some(16)
+        );
+        assert_eq!(
+            literals.find_candidate(b"INFO 1 in main, ERROR 2 in main", 17),
+            None
+        );
+        assert_eq!(literals.find_candidate(b"ERROR 2 in lib", 0), None);
+
+        let literals = self::literals("b$");
+        assert_eq!(literals.find_candidate(b"ab", 0), Some(1));
+        assert_eq!(literals.find_candidate(b"ba", 0), None);
+        assert_eq!(self::literals("").find_candidate(b"ab", 1), Some(1));
+    }
+}
@@ -1,197 +1,665 @@
-use self::pattern::{parse_pattern, Pattern};
-use std::{iter::Peekable, str::Chars};
+pub use self::pattern::{Flags, PatternError};
 
+use self::case::case_variants;
+use self::literal::Literals;
+use self::pattern::{parse_pattern_with_flags, CharacterClass, ClassItem, Pattern, PosixClass};
+use self::program::{compile, Program};
+use self::unicode::Property;
+use std::{borrow::Cow, cell::RefCell, ops::Range, sync::Arc};
+
+mod backtrack;
+mod case;
+mod dfa;
+mod literal;
 mod pattern;
+mod pikevm;
+mod program;
+#[allow(clippy::module_inception, clippy::bool_assert_comparison)]
 mod tests;
+mod unicode;
+
+// How the input and the pattern are decoded into chars
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Mode {
+    // Chars are decoded from UTF-8, and a byte that isn't part of a valid sequence matches nothing
+    #[default]
+    Unicode,
+    // Each byte is a char, the bytes from 0x80 being the Latin-1 chars
+    Bytes,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Match {
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn as_str<'a>(&self, input_string: &'a str) -> &'a str {
+        &input_string[self.range()]
+    }
+
+    pub fn as_bytes<'a>(&self, input: &'a [u8]) -> &'a [u8] {
+        &input[self.range()]
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Captures<'a> {
+    input: &'a [u8],
+    groups: Vec<Option<Match>>,
+    // The name of each group if it has one, shared with the regex
+    group_names: Arc<[Option<String>]>,
+}
+
+impl<'a> Captures<'a> {
+    // The groups recorded in slots, the slots 2n and 2n + 1 being the start and end of group n
+    fn from_slots(input: &'a [u8], slots: &[Option<usize>]) -> Self {
+        let groups = slots
+            .chunks(2)
+            .map(|slots| match slots {
+                [Some(start), Some(end)] => Some(Match {
+                    start: *start,
+                    end: *end,
+                }),
+                _ => None,
+            })
+            .collect();
+        Captures {
+            input,
+            groups,
+            group_names: Arc::new([]),
+        }
+    }
+
+    // Group 0 is the whole match, the capturing groups are numbered from 1
+    pub fn get(&self, index: usize) -> Option<Match> {
+        self.groups.get(index).copied().flatten()
+    }
+
+    pub fn name(&self, name: &str) -> Option<Match> {
+        self.group_index(name).and_then(|index| self.get(index))
+    }
+
+    pub fn name_as_str(&self, name: &str) -> Option<&'a str> {
+        self.group_index(name).and_then(|index| self.as_str(index))
+    }
+
+    fn group_index(&self, name: &str) -> Option<usize> {
+        self.group_names
+            .iter()
+            .position(|group_name| group_name.as_deref() == Some(name))
+    }
+
+    // The text of the group, unless it isn't valid UTF-8
+    pub fn as_str(&self, index: usize) -> Option<&'a str> {
+        self.as_bytes(index)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    pub fn as_bytes(&self, index: usize) -> Option<&'a [u8]> {
+        self.get(index).map(|m| m.as_bytes(self.input))
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Option<Match>> + '_ {
+        self.groups.iter().copied()
+    }
+}
+
+// A parsed pattern, to match many inputs without parsing the pattern again. It's compiled into a
+// program, that a lazy DFA runs to tell whether the input matches and the Pike VM runs to find
+// where, unless it has backreferences or lookarounds and the backtracker runs it
+#[derive(Debug, PartialEq, Clone)]
+pub struct Regex {
+    mode: Mode,
+    program: Program,
+    dfa_cache: Cache<dfa::Cache>,
+    pikevm_cache: Cache<pikevm::Cache>,
+    literals: Literals,
+    group_names: Arc<[Option<String>]>,
+}
 
-pub fn match_pattern(input_string: &str, pattern_string: &str) -> bool {
-    let patterns = parse_pattern(pattern_string);
-    let mut chars = input_string.chars().peekable();
-    let (is_match, _) = is_matching(&patterns, &mut chars, false);
-    is_match
-}
-
-fn is_matching(
-    patterns: &[Pattern],
-    mut chars: &mut Peekable<Chars>,
-    from_start: bool,
-) -> (bool, usize) {
-    let initial_chars_count = chars.clone().count();
-    for pattern in patterns {
-        loop {
-            let is_match = match pattern {
-                Pattern::Literal(c) => is_matching_literal(c, &mut chars),
-                Pattern::Digit => is_matching_digit(&mut chars),
-                Pattern::Alphanumeric => is_matching_alphanumeric(&mut chars),
-                Pattern::PositiveGroup(group) => is_matching_positive_group(group, &mut chars),
-                Pattern::NegativeGroup(group) => is_matching_negative_group(group, &mut chars),
-                Pattern::StartOfString(pattern) => {
-                    let is_match = is_matching_start_of_string(pattern, &mut chars);
-                    if !is_match {
-                        return (false, initial_chars_count - chars.count());
+impl Regex {
+    pub fn new(pattern_string: &str) -> Result<Self, PatternError> {
+        Regex::with_mode(pattern_string, Mode::Unicode)
+    }
+
+    // In the bytes mode, each byte of the pattern is a char too, so that a non-ASCII char in the
+    // pattern matches the same bytes in the input, and the pattern doesn't have to be valid UTF-8
+    pub fn with_mode(
+        pattern: &(impl AsRef<[u8]> + ?Sized),
+        mode: Mode,
+    ) -> Result<Self, PatternError> {
+        Regex::with_flags(pattern, mode, Flags::default())
+    }
+
+    // The flags are the ones that the pattern starts with, that it can change inline
+    pub fn with_flags(
+        pattern: &(impl AsRef<[u8]> + ?Sized),
+        mode: Mode,
+        flags: Flags,
+    ) -> Result<Self, PatternError> {
+        let pattern = pattern.as_ref();
+        let patterns = match mode {
+            Mode::Unicode => {
+                let pattern_string = std::str::from_utf8(pattern).map_err(|error| {
+                    let valid_string = std::str::from_utf8(&pattern[..error.valid_up_to()]);
+                    PatternError::InvalidUtf8 {
+                        column: valid_string.map_or(0, |string| string.chars().count()) + 1,
                     }
-                    true
-                }
-                Pattern::EndOfString => is_matching_end_of_string(&mut chars),
-                Pattern::ZeroOrOne(pattern) => is_matching_zero_or_one(pattern, &mut chars),
-                Pattern::OneOrMore(pattern) => is_matching_one_or_more(pattern, &mut chars),
-                Pattern::Wildcard => is_matching_wildcard(&mut chars),
-                Pattern::CapturingGroup(group) => is_matching_capturing_group(group, &mut chars),
-                Pattern::Alternation(groups) => is_matching_alternation(groups, &mut chars),
-                Pattern::Backreference(number) => {
-                    is_matching_backreference(*number, &mut chars, patterns)
-                }
-            };
-            // If it's a match, then continue with the next pattern
-            if is_match {
-                break;
+                })?;
+                parse_pattern_with_flags(pattern_string, flags)?
             }
-            // If it's not a match and it's the first pattern, then try with the next character if allowed
-            let is_first_pattern = patterns.first() == Some(pattern);
-            if is_first_pattern && !from_start {
-                if let Some(_) = chars.peek() {
-                    continue;
-                }
+            Mode::Bytes => {
+                let pattern_string: String = pattern.iter().copied().map(char::from).collect();
+                parse_pattern_with_flags(&pattern_string, flags)?
             }
-            // If it's not a match, if it's not the first pattern or if it cannot check the next char, then stop
-            return (false, initial_chars_count - chars.count());
+        };
+        let program = compile(&patterns).ok_or(PatternError::PatternTooLarge)?;
+        let mut group_names = vec![None];
+        for pattern in &patterns {
+            add_group_names(pattern, &mut group_names);
         }
+        Ok(Regex {
+            program,
+            literals: Literals::new(&patterns, mode),
+            group_names: group_names.into(),
+            mode,
+            dfa_cache: Cache::default(),
+            pikevm_cache: Cache::default(),
+        })
     }
 
-    (true, initial_chars_count - chars.count())
-}
+    pub fn is_match(&self, input: &(impl AsRef<[u8]> + ?Sized)) -> bool {
+        let input = self.input(input.as_ref());
+        let Some(position) = self.literals.find_candidate(input.bytes, 0) else {
+            return false;
+        };
+        if self.program.is_backtracked {
+            return self.find_at(input, position).is_some();
+        }
+        self.is_dfa_match_at(input, position)
+    }
 
-fn is_matching_literal(c: &char, chars: &mut Peekable<Chars>) -> bool {
-    if let Some(char) = chars.next() {
-        return *c == char;
+    pub fn find(&self, input: &(impl AsRef<[u8]> + ?Sized)) -> Option<Match> {
+        self.find_at(self.input(input.as_ref()), 0)
+    }
+
+    pub fn find_iter<'r, 'a>(&'r self, input: &'a (impl AsRef<[u8]> + ?Sized)) -> Matches<'r, 'a> {
+        Matches {
+            regex: Cow::Borrowed(self),
+            input: input.as_ref(),
+            position: 0,
+        }
+    }
+
+    pub fn captures<'a>(&self, input: &'a (impl AsRef<[u8]> + ?Sized)) -> Option<Captures<'a>> {
+        let input = self.input(input.as_ref());
+        let mut captures = self.captures_at(input, 0, self.program.slots_count)?;
+        captures.group_names = Arc::clone(&self.group_names);
+        Some(captures)
+    }
+
+    fn input<'a>(&self, bytes: &'a [u8]) -> Input<'a> {
+        Input::new(bytes, self.mode)
+    }
+
+    // Only the slots of the whole match are recorded
+    fn find_at(&self, input: Input, position: usize) -> Option<Match> {
+        self.captures_at(input, position, 2)
+            .and_then(|captures| captures.get(0))
+    }
+
+    // The groups of the first `slots_count` slots
+    fn captures_at<'a>(
+        &self,
+        input: Input<'a>,
+        position: usize,
+        slots_count: usize,
+    ) -> Option<Captures<'a>> {
+        // The literals skip the inputs that can't match, and the positions a match can't start at
+        let position = self.literals.find_candidate(input.bytes, position)?;
+        if self.program.is_backtracked {
+            return backtrack::captures_at(&self.program, &self.literals, input, position);
+        }
+        // Most inputs don't match, and the DFA tells it faster than the Pike VM
+        if !self.is_dfa_match_at(input, position) {
+            return None;
+        }
+        pikevm::captures_at(
+            &self.program,
+            slots_count,
+            &mut self.pikevm_cache.borrow_mut(),
+            &self.literals,
+            input,
+            position,
+        )
     }
-    false
-}
 
-fn is_matching_digit(chars: &mut Peekable<Chars>) -> bool {
-    if let Some(char) = chars.next() {
-        return char.is_digit(10);
+    fn is_dfa_match_at(&self, input: Input, position: usize) -> bool {
+        dfa::is_match_at(
+            &self.program,
+            &mut self.dfa_cache.borrow_mut(),
+            input,
+            position,
+        )
     }
-    false
 }
 
-fn is_matching_alphanumeric(chars: &mut Peekable<Chars>) -> bool {
-    if let Some(char) = chars.next() {
-        return char.is_alphanumeric() || char == '_';
+// The state that an engine keeps between matches, which only speeds the matching up, so it's left
+// out of the comparisons and the clones
+#[derive(Default)]
+struct Cache<T: Default>(RefCell<T>);
+
+impl<T: Default> Cache<T> {
+    fn borrow_mut(&self) -> std::cell::RefMut<'_, T> {
+        self.0.borrow_mut()
     }
-    false
 }
 
-fn is_matching_positive_group(group: &String, chars: &mut Peekable<Chars>) -> bool {
-    if let Some(char) = chars.next() {
-        return group.contains(char);
+impl<T: Default> std::fmt::Debug for Cache<T> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("Cache")
     }
-    false
 }
 
-fn is_matching_negative_group(group: &String, chars: &mut Peekable<Chars>) -> bool {
-    if let Some(char) = chars.next() {
-        return !group.contains(char);
+impl<T: Default> PartialEq for Cache<T> {
+    fn eq(&self, _: &Self) -> bool {
+        true
     }
-    false
 }
 
-fn is_matching_start_of_string(pattern: &Pattern, chars: &mut Peekable<Chars>) -> bool {
-    let (is_match, checked_chars_count) = is_matching(&[pattern.clone()], &mut chars.clone(), true);
-    if is_match {
-        chars.nth(checked_chars_count - 1);
+impl<T: Default> Clone for Cache<T> {
+    fn clone(&self) -> Self {
+        Cache::default()
     }
-    is_match
 }
 
-fn is_matching_end_of_string(chars: &mut Peekable<Chars>) -> bool {
-    match chars.next() {
-        Some(_) => false,
-        None => true,
+pub struct Matches<'r, 'a> {
+    regex: Cow<'r, Regex>,
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Iterator for Matches<'_, '_> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        if self.position > self.input.len() {
+            return None;
+        }
+        let input = self.regex.input(self.input);
+        let found = self.regex.find_at(input, self.position)?;
+        // An empty match would be found again at the same position, so step over one char
+        self.position = if found.start == found.end {
+            found.end + input.next_char(found.end).map_or(1, |(_, length)| length)
+        } else {
+            found.end
+        };
+        Some(found)
     }
 }
 
-fn is_matching_zero_or_one(pattern: &Pattern, chars: &mut Peekable<Chars>) -> bool {
-    let mut count: usize = 0;
-    loop {
-        let (is_match, checked_chars_count) =
-            is_matching(&[pattern.clone()], &mut chars.clone(), true);
-        if is_match {
-            match chars.peek() {
-                Some(_) => {
-                    chars.nth(checked_chars_count - 1);
-                }
-                None => {}
-            };
-            count += 1;
-            continue;
+pub fn match_pattern(
+    input: &(impl AsRef<[u8]> + ?Sized),
+    pattern_string: &str,
+) -> Result<bool, PatternError> {
+    Ok(Regex::new(pattern_string)?.is_match(input))
+}
+
+pub fn find(
+    input: &(impl AsRef<[u8]> + ?Sized),
+    pattern_string: &str,
+) -> Result<Option<Match>, PatternError> {
+    Ok(Regex::new(pattern_string)?.find(input))
+}
+
+pub fn find_iter<'a>(
+    input: &'a (impl AsRef<[u8]> + ?Sized),
+    pattern_string: &str,
+) -> Result<Matches<'static, 'a>, PatternError> {
+    Ok(Matches {
+        regex: Cow::Owned(Regex::new(pattern_string)?),
+        input: input.as_ref(),
+        position: 0,
+    })
+}
+
+pub fn captures<'a>(
+    input: &'a (impl AsRef<[u8]> + ?Sized),
+    pattern_string: &str,
+) -> Result<Option<Captures<'a>>, PatternError> {
+    Ok(Regex::new(pattern_string)?.captures(input))
+}
+
+// The input being matched, decoded into chars as the mode tells
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Input<'a> {
+    bytes: &'a [u8],
+    mode: Mode,
+}
+
+impl<'a> Input<'a> {
+    fn new(bytes: &'a [u8], mode: Mode) -> Self {
+        Input { bytes, mode }
+    }
+
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    // The char starting at the position and its length in bytes, unless it's the end of the
+    // input, the char being None for a byte that isn't part of a valid UTF-8 sequence
+    fn next_char(&self, position: usize) -> Option<(Option<char>, usize)> {
+        let byte = *self.bytes.get(position)?;
+        if self.mode == Mode::Bytes || byte.is_ascii() {
+            return Some((Some(char::from(byte)), 1));
         }
-        return count <= 1;
+        // A char is at most 4 bytes long
+        let bytes = &self.bytes[position..self.len().min(position + 4)];
+        let valid_string = match std::str::from_utf8(bytes) {
+            Ok(string) => string,
+            Err(error) => std::str::from_utf8(&bytes[..error.valid_up_to()]).unwrap_or_default(),
+        };
+        match valid_string.chars().next() {
+            Some(char) => Some((Some(char), char.len_utf8())),
+            None => Some((None, 1)),
+        }
+    }
+
+    // The char ending at the position and its length in bytes, unless it's the start of the input
+    fn previous_char(&self, position: usize) -> Option<(Option<char>, usize)> {
+        if position == 0 {
+            return None;
+        }
+        (1..=position.min(4)).rev().find_map(|length| {
+            self.next_char(position - length)
+                .filter(|(_, char_length)| *char_length == length)
+        })
+    }
+
+    // Whether a word char is on one side of the position but not on the other
+    fn is_word_boundary(&self, position: usize) -> bool {
+        let is_word = |char: Option<(Option<char>, usize)>| {
+            is_word_char(char.and_then(|(char, _)| char), self.mode)
+        };
+        is_word(self.previous_char(position)) != is_word(self.next_char(position))
+    }
+
+    fn is_start_of_line(&self, position: usize) -> bool {
+        position == 0 || self.bytes[position - 1] == b'\n'
+    }
+
+    fn is_end_of_line(&self, position: usize) -> bool {
+        position == self.len() || self.bytes[position] == b'\n'
+    }
+
+    fn is_end_of_last_line(&self, position: usize) -> bool {
+        position == self.len() || (position + 1 == self.len() && self.bytes[position] == b'\n')
     }
 }
 
-fn is_matching_one_or_more(pattern: &Pattern, chars: &mut Peekable<Chars>) -> bool {
-    let mut count: usize = 0;
-    loop {
-        let (is_match, checked_chars_count) =
-            is_matching(&[pattern.clone()], &mut chars.clone(), true);
-        if is_match {
-            chars.nth(checked_chars_count - 1);
-            count += 1;
-            continue;
+// Number of capturing groups in the pattern, including the nested ones
+fn groups_count(pattern: &Pattern) -> usize {
+    match pattern {
+        Pattern::Repeat { pattern, .. } => groups_count(pattern),
+        Pattern::CapturingGroup(group) | Pattern::NamedCapturingGroup(_, group) => {
+            1 + group.iter().map(groups_count).sum::<usize>()
         }
-        return count >= 1;
+        Pattern::NonCapturingGroup(group)
+        | Pattern::Lookahead {
+            patterns: group, ..
+        }
+        | Pattern::Lookbehind {
+            patterns: group, ..
+        } => group.iter().map(groups_count).sum(),
+        Pattern::Alternation(alternatives) => alternatives.iter().flatten().map(groups_count).sum(),
+        _ => 0,
     }
 }
 
-fn is_matching_wildcard(chars: &mut Peekable<Chars>) -> bool {
-    match chars.next() {
-        Some(_) => true,
-        None => false,
+// The fewest and the most chars that a match of the patterns consumes, without a most if it's
+// unbounded or it depends on the text that a group captured
+fn length_range(patterns: &[Pattern]) -> (usize, Option<usize>) {
+    patterns.iter().map(pattern_length_range).fold(
+        (0, Some(0)),
+        |(min, max), (pattern_min, pattern_max)| {
+            let max = max
+                .zip(pattern_max)
+                .and_then(|(max, pattern_max)| max.checked_add(pattern_max));
+            (min.saturating_add(pattern_min), max)
+        },
+    )
+}
+
+fn pattern_length_range(pattern: &Pattern) -> (usize, Option<usize>) {
+    match pattern {
+        Pattern::Literal(_)
+        | Pattern::Digit
+        | Pattern::Alphanumeric
+        | Pattern::Whitespace
+        | Pattern::NonDigit
+        | Pattern::NonAlphanumeric
+        | Pattern::NonWhitespace
+        | Pattern::CharacterClass(_)
+        | Pattern::UnicodeProperty { .. }
+        | Pattern::Wildcard
+        | Pattern::AnyChar => (1, Some(1)),
+        Pattern::CaseInsensitive(pattern) => pattern_length_range(pattern),
+        Pattern::StartOfString
+        | Pattern::EndOfString
+        | Pattern::EndOfLastLine
+        | Pattern::StartOfLine
+        | Pattern::EndOfLine
+        | Pattern::WordBoundary
+        | Pattern::NonWordBoundary
+        | Pattern::Lookahead { .. }
+        | Pattern::Lookbehind { .. } => (0, Some(0)),
+        Pattern::Repeat {
+            pattern, min, max, ..
+        } => {
+            let (pattern_min, pattern_max) = pattern_length_range(pattern);
+            let max = match (pattern_max, max) {
+                // Repeating a match of no chars still matches no chars
+                (Some(0), _) => Some(0),
+                (Some(pattern_max), Some(max)) => pattern_max.checked_mul(*max),
+                _ => None,
+            };
+            (pattern_min.saturating_mul(*min), max)
+        }
+        Pattern::CapturingGroup(group)
+        | Pattern::NamedCapturingGroup(_, group)
+        | Pattern::NonCapturingGroup(group) => length_range(group),
+        Pattern::Alternation(alternatives) => {
+            let ranges: Vec<_> = alternatives
+                .iter()
+                .map(|alternative| length_range(alternative))
+                .collect();
+            let min = ranges.iter().map(|(min, _)| *min).min().unwrap_or(0);
+            let max = ranges
+                .iter()
+                .try_fold(0, |most, (_, max)| max.map(|max| most.max(max)));
+            (min, max)
+        }
+        Pattern::Backreference(_) => (0, None),
     }
 }
 
-fn is_matching_capturing_group(group: &Vec<Pattern>, chars: &mut Peekable<Chars>) -> bool {
-    let (is_match, checked_chars_count) = is_matching(group, &mut chars.clone(), true);
-    if is_match {
-        chars.nth(checked_chars_count - 1);
+// Whether every match of the patterns starts at the start of the string, so that they can only be
+// tried there
+fn is_anchored(patterns: &[Pattern]) -> bool {
+    match patterns.first() {
+        Some(Pattern::StartOfString) => true,
+        Some(
+            Pattern::CapturingGroup(group)
+            | Pattern::NamedCapturingGroup(_, group)
+            | Pattern::NonCapturingGroup(group),
+        ) => is_anchored(group),
+        Some(Pattern::Alternation(alternatives)) => alternatives
+            .iter()
+            .all(|alternative| is_anchored(alternative)),
+        _ => false,
     }
-    is_match
 }
 
-fn is_matching_alternation(groups: &Vec<Vec<Pattern>>, chars: &mut Peekable<Chars>) -> bool {
-    for group in groups {
-        let (is_match, checked_chars_count) = is_matching(group, &mut chars.clone(), true);
-        if is_match {
-            chars.nth(checked_chars_count - 1);
-            return true;
+// Adds the names of the capturing groups in the pattern, None for the unnamed ones, in the order
+// of their numbers
+fn add_group_names(pattern: &Pattern, group_names: &mut Vec<Option<String>>) {
+    match pattern {
+        Pattern::Repeat { pattern, .. } => add_group_names(pattern, group_names),
+        Pattern::CapturingGroup(group) => {
+            group_names.push(None);
+            group
+                .iter()
+                .for_each(|pattern| add_group_names(pattern, group_names));
+        }
+        Pattern::NamedCapturingGroup(name, group) => {
+            group_names.push(Some(name.clone()));
+            group
+                .iter()
+                .for_each(|pattern| add_group_names(pattern, group_names));
+        }
+        Pattern::NonCapturingGroup(group)
+        | Pattern::Lookahead {
+            patterns: group, ..
         }
+        | Pattern::Lookbehind {
+            patterns: group, ..
+        } => {
+            group
+                .iter()
+                .for_each(|pattern| add_group_names(pattern, group_names));
+        }
+        Pattern::Alternation(alternatives) => alternatives
+            .iter()
+            .flatten()
+            .for_each(|pattern| add_group_names(pattern, group_names)),
+        _ => {}
     }
-    false
 }
 
-fn is_matching_backreference(
-    number: usize,
-    chars: &mut Peekable<Chars>,
-    patterns: &[Pattern],
-) -> bool {
-    let index = number - 1;
-    let pattern = patterns
-        .iter()
-        .filter(|p| match p {
-            Pattern::CapturingGroup(_) | Pattern::Alternation(_) => true,
-            _ => false,
-        })
-        .nth(index);
+// Whether the char matches a pattern that matches a single char. The classes of chars follow the
+// ASCII definitions for the ASCII chars and in the bytes mode, and the Unicode ones otherwise
+fn is_matching_char(pattern: &Pattern, char: char, mode: Mode) -> bool {
+    match pattern {
+        Pattern::Literal(c) => *c == char,
+        Pattern::Digit => is_matching_posix_class(PosixClass::Digit, char, mode),
+        Pattern::Alphanumeric => is_alphanumeric(char, mode),
+        Pattern::Whitespace => is_matching_posix_class(PosixClass::Space, char, mode),
+        Pattern::NonDigit => !is_matching_posix_class(PosixClass::Digit, char, mode),
+        Pattern::NonAlphanumeric => !is_alphanumeric(char, mode),
+        Pattern::NonWhitespace => !is_matching_posix_class(PosixClass::Space, char, mode),
+        Pattern::CharacterClass(class) => is_matching_class(class, char, mode),
+        Pattern::UnicodeProperty {
+            property,
+            is_negated,
+        } => is_matching_unicode_property(property, char, mode) != *is_negated,
+        Pattern::Wildcard => char != '\n',
+        Pattern::AnyChar => true,
+        // A negated class matches the chars whose cases are all outside of it
+        Pattern::CaseInsensitive(pattern) => match &**pattern {
+            Pattern::CharacterClass(class) => {
+                let is_any_case_matching = case_variants(char, mode)
+                    .any(|char| is_matching_class_items(class, char, mode));
+                is_any_case_matching != class.is_negated
+            }
+            Pattern::UnicodeProperty {
+                property,
+                is_negated,
+            } => {
+                let is_any_case_matching = case_variants(char, mode)
+                    .any(|char| is_matching_unicode_property(property, char, mode));
+                is_any_case_matching != *is_negated
+            }
+            pattern => case_variants(char, mode).any(|char| is_matching_char(pattern, char, mode)),
+        },
+        _ => false,
+    }
+}
+
+fn is_matching_class(class: &CharacterClass, char: char, mode: Mode) -> bool {
+    is_matching_class_items(class, char, mode) != class.is_negated
+}
+
+// Whether any item of the class matches the char, regardless of the negation
+fn is_matching_class_items(class: &CharacterClass, char: char, mode: Mode) -> bool {
+    class.items.iter().any(|item| match item {
+        ClassItem::Char(c) => *c == char,
+        ClassItem::Range(start, end) => (*start..=*end).contains(&char),
+        ClassItem::Digit => is_matching_posix_class(PosixClass::Digit, char, mode),
+        ClassItem::Alphanumeric => is_alphanumeric(char, mode),
+        ClassItem::Whitespace => is_matching_posix_class(PosixClass::Space, char, mode),
+        ClassItem::NonDigit => !is_matching_posix_class(PosixClass::Digit, char, mode),
+        ClassItem::NonAlphanumeric => !is_alphanumeric(char, mode),
+        ClassItem::NonWhitespace => !is_matching_posix_class(PosixClass::Space, char, mode),
+        ClassItem::Posix(posix_class) => is_matching_posix_class(*posix_class, char, mode),
+        ClassItem::UnicodeProperty {
+            property,
+            is_negated,
+        } => is_matching_unicode_property(property, char, mode) != *is_negated,
+    })
+}
 
-    if let Some(pattern) = pattern {
-        let (is_match, checked_chars_count) =
-            is_matching(&[pattern.clone()], &mut chars.clone(), true);
-        if is_match {
-            chars.nth(checked_chars_count - 1);
-            return true;
+// In the bytes mode, only the ASCII chars are Unicode chars
+fn is_matching_unicode_property(property: &Property, char: char, mode: Mode) -> bool {
+    (char.is_ascii() || mode == Mode::Unicode) && property.contains(char)
+}
+
+fn is_alphanumeric(char: char, mode: Mode) -> bool {
+    char == '_' || is_matching_posix_class(PosixClass::Alnum, char, mode)
+}
+
+// The chars of the words that the word boundaries are around, an invalid byte not being one
+fn is_word_char(char: Option<char>, mode: Mode) -> bool {
+    char.is_some_and(|char| is_alphanumeric(char, mode))
+}
+
+fn is_matching_posix_class(posix_class: PosixClass, char: char, mode: Mode) -> bool {
+    if char.is_ascii() || mode == Mode::Bytes {
+        return match posix_class {
+            PosixClass::Alnum => char.is_ascii_alphanumeric(),
+            PosixClass::Alpha => char.is_ascii_alphabetic(),
+            PosixClass::Blank => char == ' ' || char == '\t',
+            PosixClass::Cntrl => char.is_ascii_control(),
+            PosixClass::Digit => char.is_ascii_digit(),
+            PosixClass::Graph => char.is_ascii_graphic(),
+            PosixClass::Lower => char.is_ascii_lowercase(),
+            PosixClass::Print => char == ' ' || char.is_ascii_graphic(),
+            PosixClass::Punct => char.is_ascii_punctuation(),
+            // Unlike `is_ascii_whitespace`, the vertical tab is a space
+            PosixClass::Space => char.is_ascii_whitespace() || char == '\x0b',
+            PosixClass::Upper => char.is_ascii_uppercase(),
+            PosixClass::Xdigit => char.is_ascii_hexdigit(),
+        };
+    }
+    let is_graph = !char.is_whitespace() && !char.is_control();
+    match posix_class {
+        PosixClass::Alnum => char.is_alphanumeric(),
+        PosixClass::Alpha => char.is_alphabetic(),
+        // The spaces that don't break the line
+        PosixClass::Blank => {
+            char.is_whitespace() && !matches!(char, '\u{85}' | '\u{2028}' | '\u{2029}')
         }
+        PosixClass::Cntrl => char.is_control(),
+        // Like in grep, the digits and hexadecimal digits are only the ASCII ones
+        PosixClass::Digit | PosixClass::Xdigit => false,
+        PosixClass::Graph => is_graph,
+        PosixClass::Lower => char.is_lowercase(),
+        PosixClass::Print => !char.is_control(),
+        PosixClass::Punct => is_graph && !char.is_alphanumeric(),
+        PosixClass::Space => char.is_whitespace(),
+        PosixClass::Upper => char.is_uppercase(),
     }
-
-    false
 }
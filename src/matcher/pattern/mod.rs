@@ -1,142 +1,864 @@
+use super::{length_range, unicode::Property};
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+use thiserror::Error;
+
+#[allow(clippy::module_inception)]
 mod tests;
 
 const ESCAPE_SYMBOL: char = '\\';
 const DIGIT_SYMBOL: char = 'd';
 const ALPHANUMERIC_SYMBOL: char = 'w';
+const WHITESPACE_SYMBOL: char = 's';
+const NON_DIGIT_SYMBOL: char = 'D';
+const NON_ALPHANUMERIC_SYMBOL: char = 'W';
+const NON_WHITESPACE_SYMBOL: char = 'S';
+const UNICODE_PROPERTY_SYMBOL: char = 'p';
+const NEGATED_UNICODE_PROPERTY_SYMBOL: char = 'P';
+const UNICODE_PROPERTY_START_SYMBOL: char = '{';
+const UNICODE_PROPERTY_END_SYMBOL: char = '}';
+const START_OF_STRING_ESCAPE_SYMBOL: char = 'A';
+const END_OF_STRING_ESCAPE_SYMBOL: char = 'z';
+const END_OF_LAST_LINE_SYMBOL: char = 'Z';
+const WORD_BOUNDARY_SYMBOL: char = 'b';
+const NON_WORD_BOUNDARY_SYMBOL: char = 'B';
 const START_OF_STRING_SYMBOL: char = '^';
 const END_OF_STRING_SYMBOL: char = '$';
 const ZERO_OR_ONE_SYMBOL: char = '?';
 const ONE_OR_MORE_SYMBOL: char = '+';
-const POSITIVE_NEGATIVE_GROUP_START_SYMBOL: char = '[';
-const POSITIVE_NEGATIVE_GROUP_END_SYMBOL: char = ']';
-const NEGATIVE_GROUP_SYMBOL: char = '^';
+const ZERO_OR_MORE_SYMBOL: char = '*';
+const REPEAT_START_SYMBOL: char = '{';
+const REPEAT_END_SYMBOL: char = '}';
+const REPEAT_SEPARATOR_SYMBOL: char = ',';
+const LAZY_SYMBOL: char = '?';
+const CHARACTER_CLASS_START_SYMBOL: char = '[';
+const CHARACTER_CLASS_END_SYMBOL: char = ']';
+const NEGATED_CLASS_SYMBOL: char = '^';
+const CLASS_RANGE_SYMBOL: char = '-';
+const POSIX_CLASS_START_SYMBOL: char = '[';
+const POSIX_CLASS_DELIMITER_SYMBOL: char = ':';
 const WILDCARD_SYMBOL: char = '.';
 const CAPTURING_GROUP_START_SYMBOL: char = '(';
 const CAPTURING_GROUP_END_SYMBOL: char = ')';
+const GROUP_EXTENSION_SYMBOL: char = '?';
+const NON_CAPTURING_GROUP_SYMBOL: char = ':';
+const PYTHON_GROUP_NAME_SYMBOL: char = 'P';
+const GROUP_NAME_START_SYMBOL: char = '<';
+const GROUP_NAME_END_SYMBOL: char = '>';
+const NAMED_BACKREFERENCE_SYMBOL: char = 'k';
+const LOOKBEHIND_SYMBOL: char = '<';
+const POSITIVE_LOOKAROUND_SYMBOL: char = '=';
+const NEGATIVE_LOOKAROUND_SYMBOL: char = '!';
+const CASE_INSENSITIVE_FLAG: char = 'i';
+const MULTILINE_FLAG: char = 'm';
+const DOT_ALL_FLAG: char = 's';
+const EXTENDED_FLAG: char = 'x';
+const NEGATED_FLAGS_SYMBOL: char = '-';
+const COMMENT_SYMBOL: char = '#';
+const NEWLINE: char = '\n';
 const ALTERNATION_SEPARATOR_SYMBOL: char = '|';
 
+// Change how the patterns match, for the whole pattern or set inline like "(?i)" for the rest of
+// the group, or "(?i:...)" for a group
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Flags {
+    pub is_case_insensitive: bool,
+    // "^" and "$" match at the start and end of the lines too
+    pub is_multiline: bool,
+    // "." matches a newline too
+    pub is_dot_all: bool,
+    // Whitespace and comments from "#" to the end of the line are ignored, outside of classes
+    pub is_extended: bool,
+}
+
+// Columns are 1-based and count chars, pointing at the symbol that made the pattern invalid
+#[derive(Debug, PartialEq, Clone, Error)]
+pub enum PatternError {
+    #[error("unclosed character group at column {column}")]
+    UnclosedCharacterGroup { column: usize },
+    #[error("unclosed capturing group at column {column}")]
+    UnclosedCapturingGroup { column: usize },
+    #[error("incomplete escape sequence at column {column}")]
+    IncompleteEscapeSequence { column: usize },
+    #[error("nothing to repeat at column {column}")]
+    NothingToRepeat { column: usize },
+    #[error("repetition minimum greater than its maximum at column {column}")]
+    InvalidRepeatRange { column: usize },
+    #[error("invalid character range at column {column}")]
+    InvalidClassRange { column: usize },
+    #[error("unknown character class name at column {column}")]
+    UnknownPosixClass { column: usize },
+    #[error("unknown Unicode property at column {column}")]
+    UnknownUnicodeProperty { column: usize },
+    #[error("invalid group name at column {column}")]
+    InvalidGroupName { column: usize },
+    #[error("group name defined more than once at column {column}")]
+    DuplicateGroupName { column: usize },
+    #[error("reference to an undefined group name at column {column}")]
+    UnknownGroupName { column: usize },
+    #[error("lookbehind of unbounded length at column {column}")]
+    UnboundedLookbehind { column: usize },
+    #[error("invalid UTF-8 at column {column}")]
+    InvalidUtf8 { column: usize },
+    #[error("invalid back reference at column {column}")]
+    InvalidBackreference { column: usize },
+    #[error("pattern too large")]
+    PatternTooLarge,
+}
+
+impl PatternError {
+    pub fn column(&self) -> usize {
+        match self {
+            PatternError::UnclosedCharacterGroup { column }
+            | PatternError::UnclosedCapturingGroup { column }
+            | PatternError::IncompleteEscapeSequence { column }
+            | PatternError::NothingToRepeat { column }
+            | PatternError::InvalidRepeatRange { column }
+            | PatternError::InvalidClassRange { column }
+            | PatternError::UnknownPosixClass { column }
+            | PatternError::UnknownUnicodeProperty { column }
+            | PatternError::InvalidGroupName { column }
+            | PatternError::DuplicateGroupName { column }
+            | PatternError::UnknownGroupName { column }
+            | PatternError::UnboundedLookbehind { column }
+            | PatternError::InvalidUtf8 { column }
+            | PatternError::InvalidBackreference { column } => *column,
+            // The whole pattern is at fault, so it's its start
+            PatternError::PatternTooLarge => 1,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Pattern {
     Literal(char),
     Digit,
     Alphanumeric,
-    PositiveGroup(String),
-    NegativeGroup(String),
-    StartOfString(Box<Self>),
+    Whitespace,
+    NonDigit,
+    NonAlphanumeric,
+    NonWhitespace,
+    CharacterClass(CharacterClass),
+    // A char with a general category or a script, or without it if it's negated
+    UnicodeProperty {
+        property: Property,
+        is_negated: bool,
+    },
+    // Zero-width, at the start of the string
+    StartOfString,
+    // Zero-width, at the end of the string
     EndOfString,
-    ZeroOrOne(Box<Self>),
-    OneOrMore(Box<Self>),
+    // Zero-width, at the end of the string or before a newline ending it
+    EndOfLastLine,
+    // Zero-width, between a word char and either a non-word char or the start or end of string
+    WordBoundary,
+    NonWordBoundary,
+    // Repeats the pattern at least `min` times and at most `max` times, if there is a maximum,
+    // preferring as many repetitions as possible unless it's lazy
+    Repeat {
+        pattern: Box<Self>,
+        min: usize,
+        max: Option<usize>,
+        is_lazy: bool,
+    },
     Wildcard,
+    // A wildcard matching a newline too
+    AnyChar,
+    // Zero-width, at the start of the string or after a newline
+    StartOfLine,
+    // Zero-width, at the end of the string or before a newline
+    EndOfLine,
+    // Matches like the pattern, a literal, a character class, a Unicode property or a
+    // backreference, but ignoring the case of the chars
+    CaseInsensitive(Box<Self>),
     CapturingGroup(Vec<Self>),
+    NamedCapturingGroup(String, Vec<Self>),
+    NonCapturingGroup(Vec<Self>),
+    // Zero-width, where the patterns match from the position, or don't if it's negated
+    Lookahead {
+        patterns: Vec<Self>,
+        is_negated: bool,
+    },
+    // Zero-width, where the patterns match up to the position, or don't if it's negated
+    Lookbehind {
+        patterns: Vec<Self>,
+        is_negated: bool,
+    },
     Alternation(Vec<Vec<Self>>),
     Backreference(usize),
 }
 
-pub fn parse_pattern(pattern_string: &str) -> Vec<Pattern> {
-    let mut patterns = Vec::new();
-    let mut chars = pattern_string.chars().peekable();
+// Matches a char that is one of the items, or none of them if it's negated
+#[derive(Debug, PartialEq, Clone)]
+pub struct CharacterClass {
+    pub is_negated: bool,
+    pub items: Vec<ClassItem>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ClassItem {
+    Char(char),
+    // Both ends are included
+    Range(char, char),
+    Digit,
+    Alphanumeric,
+    Whitespace,
+    NonDigit,
+    NonAlphanumeric,
+    NonWhitespace,
+    Posix(PosixClass),
+    UnicodeProperty {
+        property: Property,
+        is_negated: bool,
+    },
+}
+
+// The classes named in a bracket expression like "[[:alpha:]]"
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PosixClass {
+    Alnum,
+    Alpha,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Xdigit,
+}
+
+impl PosixClass {
+    fn from_name(name: &str) -> Option<Self> {
+        let posix_class = match name {
+            "alnum" => PosixClass::Alnum,
+            "alpha" => PosixClass::Alpha,
+            "blank" => PosixClass::Blank,
+            "cntrl" => PosixClass::Cntrl,
+            "digit" => PosixClass::Digit,
+            "graph" => PosixClass::Graph,
+            "lower" => PosixClass::Lower,
+            "print" => PosixClass::Print,
+            "punct" => PosixClass::Punct,
+            "space" => PosixClass::Space,
+            "upper" => PosixClass::Upper,
+            "xdigit" => PosixClass::Xdigit,
+            _ => return None,
+        };
+        Some(posix_class)
+    }
+}
+
+// The patterns are parsed with the flags of the regex, that the tests mostly leave to the defaults
+#[cfg(test)]
+pub fn parse_pattern(pattern_string: &str) -> Result<Vec<Pattern>, PatternError> {
+    parse_pattern_with_flags(pattern_string, Flags::default())
+}
+
+pub fn parse_pattern_with_flags(
+    pattern_string: &str,
+    flags: Flags,
+) -> Result<Vec<Pattern>, PatternError> {
+    let mut parser = Parser {
+        flags,
+        chars: pattern_string.chars().peekable(),
+        chars_count: pattern_string.chars().count(),
+        groups_count: 0,
+        group_numbers: HashMap::new(),
+        backreference_columns: Vec::new(),
+    };
+    let patterns = parser.parse_alternation(false)?;
+    // Like in grep, a backreference to a group that the pattern doesn't have is an error, rather
+    // than never matching
+    for (number, column) in parser.backreference_columns {
+        if number > parser.groups_count {
+            return Err(PatternError::InvalidBackreference { column });
+        }
+    }
+    Ok(patterns)
+}
+
+// A recursive descent parser, each method parsing a level of the grammar from the chars left
+struct Parser<'p> {
+    // The flags for the rest of the current group
+    flags: Flags,
+    chars: Peekable<Chars<'p>>,
+    chars_count: usize,
+    // Number of the capturing groups opened so far
+    groups_count: usize,
+    // Numbers of the named groups opened so far, that the named backreferences point to
+    group_numbers: HashMap<String, usize>,
+    // The numbers of the backreferences with their columns, checked once all the groups are known
+    backreference_columns: Vec<(usize, usize)>,
+}
+
+enum GroupKind {
+    Capturing,
+    Named(String),
+    NonCapturing,
+    Lookahead { is_negated: bool },
+    Lookbehind { is_negated: bool },
+}
+
+impl Parser<'_> {
+    // Column of the last char taken
+    fn column(&self) -> usize {
+        self.chars_count - self.chars.clone().count()
+    }
+
+    // Parses the alternatives separated by "|" up to the end of the pattern, or up to the ")"
+    // closing the group if it's in one, which is left to the caller
+    fn parse_alternation(&mut self, is_in_group: bool) -> Result<Vec<Pattern>, PatternError> {
+        let mut alternatives = vec![self.parse_sequence(is_in_group)?];
+        while self
+            .chars
+            .next_if_eq(&ALTERNATION_SEPARATOR_SYMBOL)
+            .is_some()
+        {
+            alternatives.push(self.parse_sequence(is_in_group)?);
+        }
+        if alternatives.len() == 1 {
+            return Ok(alternatives.remove(0));
+        }
+        Ok(vec![Pattern::Alternation(alternatives)])
+    }
 
-    while let Some(char) = chars.next() {
-        // Groups
-        if char == POSITIVE_NEGATIVE_GROUP_START_SYMBOL {
-            let mut group = String::new();
-            while let Some(char) = chars.next() {
-                if char == POSITIVE_NEGATIVE_GROUP_END_SYMBOL {
-                    break;
+    // Parses the patterns of an alternative, matched one after the other
+    fn parse_sequence(&mut self, is_in_group: bool) -> Result<Vec<Pattern>, PatternError> {
+        let mut patterns = Vec::new();
+        while let Some(pattern) = self.parse_piece(is_in_group)? {
+            patterns.push(pattern);
+        }
+        Ok(patterns)
+    }
+
+    // Parses a pattern with the repetitions that follow it, unless the alternative is over
+    fn parse_piece(&mut self, is_in_group: bool) -> Result<Option<Pattern>, PatternError> {
+        while self.parse_flags_setting() {}
+        if self.is_alternative_end(is_in_group) {
+            return Ok(None);
+        }
+
+        let Some(char) = self.chars.next() else {
+            return Ok(None);
+        };
+        let mut pattern = self.parse_atom(char)?;
+        // The chars that can't have another case stay literals, so that the literal prefix can
+        // be found
+        let is_case_insensitive = match &pattern {
+            Pattern::Literal(c) => c.is_alphabetic(),
+            Pattern::CharacterClass(_)
+            | Pattern::UnicodeProperty { .. }
+            | Pattern::Backreference(_) => true,
+            _ => false,
+        };
+        if is_case_insensitive && self.flags.is_case_insensitive {
+            pattern = Pattern::CaseInsensitive(Box::new(pattern));
+        }
+        // The anchors can't be repeated, so a repetition after one has nothing to repeat
+        if matches!(
+            pattern,
+            Pattern::StartOfString
+                | Pattern::EndOfString
+                | Pattern::EndOfLastLine
+                | Pattern::StartOfLine
+                | Pattern::EndOfLine
+        ) {
+            return Ok(Some(pattern));
+        }
+
+        // Repetitions
+        loop {
+            self.skip_extended_whitespace();
+            let mut repeat_chars = self.chars.clone();
+            let repeat_range = match repeat_chars.next() {
+                Some(ZERO_OR_ONE_SYMBOL) => Some((0, Some(1))),
+                Some(ONE_OR_MORE_SYMBOL) => Some((1, None)),
+                Some(ZERO_OR_MORE_SYMBOL) => Some((0, None)),
+                // A brace that doesn't start a valid range is a literal, like in grep
+                Some(REPEAT_START_SYMBOL) => parse_repeat_range(&mut repeat_chars),
+                _ => None,
+            };
+            let Some((min, max)) = repeat_range else {
+                return Ok(Some(pattern));
+            };
+            self.chars.next();
+            let symbol_column = self.column();
+            self.chars = repeat_chars;
+            if max.is_some_and(|max| min > max) {
+                return Err(PatternError::InvalidRepeatRange {
+                    column: symbol_column,
+                });
+            }
+            // A following "?" makes the repetition lazy, instead of repeating it zero or one times
+            let is_lazy = self.chars.next_if_eq(&LAZY_SYMBOL).is_some();
+            pattern = Pattern::Repeat {
+                pattern: Box::new(pattern),
+                min,
+                max,
+                is_lazy,
+            };
+        }
+    }
+
+    // Parses a pattern that can be repeated, starting with the char just taken
+    fn parse_atom(&mut self, char: char) -> Result<Pattern, PatternError> {
+        let symbol_column = self.column();
+
+        match char {
+            // Character classes
+            CHARACTER_CLASS_START_SYMBOL => {
+                let chars_count = self.chars_count;
+                let column = |chars: &Peekable<Chars>| chars_count - chars.clone().count();
+                let class = parse_character_class(&mut self.chars, symbol_column, &column)?;
+                Ok(Pattern::CharacterClass(class))
+            }
+
+            // Start and end of string, or of line in the multiline mode
+            START_OF_STRING_SYMBOL if self.flags.is_multiline => Ok(Pattern::StartOfLine),
+            START_OF_STRING_SYMBOL => Ok(Pattern::StartOfString),
+            END_OF_STRING_SYMBOL if self.flags.is_multiline => Ok(Pattern::EndOfLine),
+            END_OF_STRING_SYMBOL => Ok(Pattern::EndOfString),
+
+            // Escape sequences
+            ESCAPE_SYMBOL => {
+                if let Some((property, is_negated)) =
+                    parse_unicode_property(&mut self.chars, symbol_column)?
+                {
+                    return Ok(Pattern::UnicodeProperty {
+                        property,
+                        is_negated,
+                    });
+                }
+                let pattern = match self.chars.next() {
+                    Some(DIGIT_SYMBOL) => Pattern::Digit,
+                    Some(ALPHANUMERIC_SYMBOL) => Pattern::Alphanumeric,
+                    Some(WHITESPACE_SYMBOL) => Pattern::Whitespace,
+                    Some(NON_DIGIT_SYMBOL) => Pattern::NonDigit,
+                    Some(NON_ALPHANUMERIC_SYMBOL) => Pattern::NonAlphanumeric,
+                    Some(NON_WHITESPACE_SYMBOL) => Pattern::NonWhitespace,
+                    Some(START_OF_STRING_ESCAPE_SYMBOL) => Pattern::StartOfString,
+                    Some(END_OF_STRING_ESCAPE_SYMBOL) => Pattern::EndOfString,
+                    Some(END_OF_LAST_LINE_SYMBOL) => Pattern::EndOfLastLine,
+                    Some(WORD_BOUNDARY_SYMBOL) => Pattern::WordBoundary,
+                    Some(NON_WORD_BOUNDARY_SYMBOL) => Pattern::NonWordBoundary,
+                    Some(NAMED_BACKREFERENCE_SYMBOL)
+                        if self.chars.next_if_eq(&GROUP_NAME_START_SYMBOL).is_some() =>
+                    {
+                        let name_column = self.column() + 1;
+                        let name = self.parse_group_name()?;
+                        let Some(&number) = self.group_numbers.get(&name) else {
+                            return Err(PatternError::UnknownGroupName {
+                                column: name_column,
+                            });
+                        };
+                        Pattern::Backreference(number)
+                    }
+                    Some(c) if c != '0' && c.is_ascii_digit() => {
+                        self.parse_backreference(c, symbol_column)
+                    }
+                    Some(c) => Pattern::Literal(c),
+                    None => {
+                        return Err(PatternError::IncompleteEscapeSequence {
+                            column: self.column(),
+                        })
+                    }
+                };
+                Ok(pattern)
+            }
+
+            // Groups, the capturing ones being numbered by the position of their "(" among the
+            // other ones
+            CAPTURING_GROUP_START_SYMBOL => {
+                // The flags set in the group are only for the group
+                let flags = self.flags;
+                let group_kind = self.parse_group_kind()?;
+                if matches!(group_kind, GroupKind::Capturing | GroupKind::Named(_)) {
+                    self.groups_count += 1;
                 }
-                group.push(char);
+                let patterns = self.parse_alternation(true)?;
+                if self.chars.next_if_eq(&CAPTURING_GROUP_END_SYMBOL).is_none() {
+                    return Err(PatternError::UnclosedCapturingGroup {
+                        column: symbol_column,
+                    });
+                }
+                self.flags = flags;
+                let group = match group_kind {
+                    GroupKind::Capturing => Pattern::CapturingGroup(patterns),
+                    GroupKind::Named(name) => Pattern::NamedCapturingGroup(name, patterns),
+                    GroupKind::NonCapturing => Pattern::NonCapturingGroup(patterns),
+                    GroupKind::Lookahead { is_negated } => Pattern::Lookahead {
+                        patterns,
+                        is_negated,
+                    },
+                    // The starts of a lookbehind are only tried up to its longest match back
+                    GroupKind::Lookbehind { .. } if length_range(&patterns).1.is_none() => {
+                        return Err(PatternError::UnboundedLookbehind {
+                            column: symbol_column,
+                        });
+                    }
+                    GroupKind::Lookbehind { is_negated } => Pattern::Lookbehind {
+                        patterns,
+                        is_negated,
+                    },
+                };
+                Ok(group)
+            }
+
+            // A repetition needs a pattern before it
+            ZERO_OR_ONE_SYMBOL | ONE_OR_MORE_SYMBOL | ZERO_OR_MORE_SYMBOL => {
+                Err(PatternError::NothingToRepeat {
+                    column: symbol_column,
+                })
             }
-            if group.starts_with(NEGATIVE_GROUP_SYMBOL) {
-                patterns.push(Pattern::NegativeGroup(group[1..].to_string()));
-            } else {
-                patterns.push(Pattern::PositiveGroup(group));
+            REPEAT_START_SYMBOL if parse_repeat_range(&mut self.chars.clone()).is_some() => {
+                Err(PatternError::NothingToRepeat {
+                    column: symbol_column,
+                })
             }
-            continue;
-        }
 
-        // Start of string
-        if char == START_OF_STRING_SYMBOL {
-            let remaining_pattern_string = chars.clone().collect::<String>();
-            let following_patterns = parse_pattern(&remaining_pattern_string);
-            let Some(first_pattern) = following_patterns.first() else {
-                panic!("Invalid pattern");
-            };
-            patterns.push(Pattern::StartOfString(Box::new(first_pattern.clone())));
-            patterns.append(&mut following_patterns[1..].to_vec());
-            return patterns;
+            // Wildcard
+            WILDCARD_SYMBOL if self.flags.is_dot_all => Ok(Pattern::AnyChar),
+            WILDCARD_SYMBOL => Ok(Pattern::Wildcard),
+
+            // Literal, including a ")" that doesn't close a group, like in grep
+            c => Ok(Pattern::Literal(c)),
         }
+    }
 
-        // End of string
-        if char == END_OF_STRING_SYMBOL {
-            patterns.push(Pattern::EndOfString);
-            if chars.next() != None {
-                panic!("End of string pattern in the wrong position");
+    // Parses the number of a group from its first digit. The following digits are taken as long
+    // as that many groups were opened before, so that "\10" is "\1" followed by "0" unless there
+    // are 10 groups
+    fn parse_backreference(&mut self, first_digit: char, column: usize) -> Pattern {
+        let mut number = first_digit.to_digit(10).unwrap() as usize;
+        while let Some(digit) = self.chars.peek().and_then(|c| c.to_digit(10)) {
+            let next_number = 10 * number + digit as usize;
+            if next_number > self.groups_count {
+                break;
             }
-            break;
+            number = next_number;
+            self.chars.next();
         }
+        self.backreference_columns.push((number, column));
+        Pattern::Backreference(number)
+    }
 
-        // Escape sequences
-        if char == ESCAPE_SYMBOL {
-            match chars.next() {
-                Some(DIGIT_SYMBOL) => patterns.push(Pattern::Digit),
-                Some(ALPHANUMERIC_SYMBOL) => patterns.push(Pattern::Alphanumeric),
-                Some(c) if c != '0' && c.is_digit(10) => patterns.push(Pattern::Backreference(
-                    c.to_string().parse::<usize>().unwrap(),
-                )),
-                Some(c) => patterns.push(Pattern::Literal(c)),
-                None => panic!("Invalid escape sequence"),
+    // Parses what follows the "(" of a group: "?:" or "?flags:" for a non-capturing group, "?="
+    // or "?!" for a lookahead, "?<=" or "?<!" for a lookbehind, "?<name>" or "?P<name>" for a
+    // named group, and nothing for the other ones
+    fn parse_group_kind(&mut self) -> Result<GroupKind, PatternError> {
+        let mut kind_chars = self.chars.clone();
+        if kind_chars.next() != Some(GROUP_EXTENSION_SYMBOL) {
+            return Ok(GroupKind::Capturing);
+        }
+        let mut flags_chars = kind_chars.clone();
+        if let Some((flags, NON_CAPTURING_GROUP_SYMBOL)) = parse_flags(&mut flags_chars, self.flags)
+        {
+            self.flags = flags;
+            self.chars = flags_chars;
+            return Ok(GroupKind::NonCapturing);
+        }
+        if kind_chars.next_if_eq(&NON_CAPTURING_GROUP_SYMBOL).is_some() {
+            self.chars = kind_chars;
+            return Ok(GroupKind::NonCapturing);
+        }
+        let mut lookaround_chars = kind_chars.clone();
+        let is_lookbehind = lookaround_chars.next_if_eq(&LOOKBEHIND_SYMBOL).is_some();
+        let is_negated = match lookaround_chars.next() {
+            Some(POSITIVE_LOOKAROUND_SYMBOL) => Some(false),
+            Some(NEGATIVE_LOOKAROUND_SYMBOL) => Some(true),
+            _ => None,
+        };
+        if let Some(is_negated) = is_negated {
+            self.chars = lookaround_chars;
+            if is_lookbehind {
+                return Ok(GroupKind::Lookbehind { is_negated });
             }
-            continue;
+            return Ok(GroupKind::Lookahead { is_negated });
         }
+        kind_chars.next_if_eq(&PYTHON_GROUP_NAME_SYMBOL);
+        if kind_chars.next_if_eq(&GROUP_NAME_START_SYMBOL).is_none() {
+            // The "?" is left to be parsed as a repetition of nothing, like in grep
+            return Ok(GroupKind::Capturing);
+        }
+        self.chars = kind_chars;
+        let name_column = self.column() + 1;
+        let name = self.parse_group_name()?;
+        if self.group_numbers.contains_key(&name) {
+            return Err(PatternError::DuplicateGroupName {
+                column: name_column,
+            });
+        }
+        self.group_numbers
+            .insert(name.clone(), self.groups_count + 1);
+        Ok(GroupKind::Named(name))
+    }
 
-        // Capturing or alternation group
-        if char == CAPTURING_GROUP_START_SYMBOL {
-            let mut alternation_string = String::new();
-            while let Some(char) = chars.next() {
-                if char == CAPTURING_GROUP_END_SYMBOL {
-                    break;
+    // Parses the "name>" following a "<", the name being made of word chars and not starting
+    // with a digit
+    fn parse_group_name(&mut self) -> Result<String, PatternError> {
+        let error = PatternError::InvalidGroupName {
+            column: self.column() + 1,
+        };
+        let mut name = String::new();
+        loop {
+            match self.chars.next() {
+                Some(GROUP_NAME_END_SYMBOL) if !name.is_empty() => return Ok(name),
+                Some(c)
+                    if c == '_' || c.is_alphanumeric() && !(name.is_empty() && c.is_numeric()) =>
+                {
+                    name.push(c)
                 }
-                alternation_string.push(char);
+                _ => return Err(error),
             }
-            let patterns_groups: Vec<Vec<Pattern>> = alternation_string
-                .split(ALTERNATION_SEPARATOR_SYMBOL)
-                .map(parse_pattern)
-                .collect();
-            if patterns_groups.len() == 1 {
-                patterns.push(Pattern::CapturingGroup(patterns_groups[0].clone()));
-            } else {
-                patterns.push(Pattern::Alternation(patterns_groups));
+        }
+    }
+
+    // Parses a "(?flags)" setting the flags for the rest of the group, returning whether there
+    // was one
+    fn parse_flags_setting(&mut self) -> bool {
+        self.skip_extended_whitespace();
+        let mut flags_chars = self.chars.clone();
+        if flags_chars.next() != Some(CAPTURING_GROUP_START_SYMBOL)
+            || flags_chars.next() != Some(GROUP_EXTENSION_SYMBOL)
+        {
+            return false;
+        }
+        match parse_flags(&mut flags_chars, self.flags) {
+            Some((flags, CAPTURING_GROUP_END_SYMBOL)) => {
+                self.flags = flags;
+                self.chars = flags_chars;
+                true
             }
-            continue;
+            _ => false,
         }
+    }
 
-        // Zero or one
-        if char == ZERO_OR_ONE_SYMBOL {
-            let Some(previous_pattern) = patterns.pop() else {
-                panic!("Invalid pattern");
-            };
-            patterns.push(Pattern::ZeroOrOne(Box::new(previous_pattern)));
-            continue;
+    // Skips the whitespace and the comments in the extended mode
+    fn skip_extended_whitespace(&mut self) {
+        if !self.flags.is_extended {
+            return;
+        }
+        loop {
+            if self.chars.next_if(|c| c.is_whitespace()).is_some() {
+                continue;
+            }
+            if self.chars.next_if_eq(&COMMENT_SYMBOL).is_none() {
+                return;
+            }
+            self.chars.by_ref().find(|&c| c == NEWLINE);
         }
+    }
 
-        // One or more
-        if char == ONE_OR_MORE_SYMBOL {
-            let Some(previous_pattern) = patterns.pop() else {
-                panic!("Invalid pattern");
-            };
-            patterns.push(Pattern::OneOrMore(Box::new(previous_pattern)));
-            continue;
+    // Whether the alternative ends before the next char, at a "|", the end of the pattern or the
+    // ")" closing the group
+    fn is_alternative_end(&mut self, is_in_group: bool) -> bool {
+        self.skip_extended_whitespace();
+        match self.chars.peek() {
+            None | Some(&ALTERNATION_SEPARATOR_SYMBOL) => true,
+            Some(&CAPTURING_GROUP_END_SYMBOL) => is_in_group,
+            Some(_) => false,
+        }
+    }
+}
+
+// Parses the chars following a "[" up to the closing "]". Like in grep, a "]" right after the "["
+// or "[^" and a "-" at the start or the end of the class are literals
+fn parse_character_class(
+    chars: &mut Peekable<Chars>,
+    start_column: usize,
+    column: &impl Fn(&Peekable<Chars>) -> usize,
+) -> Result<CharacterClass, PatternError> {
+    let unclosed_error = PatternError::UnclosedCharacterGroup {
+        column: start_column,
+    };
+    let is_negated = chars.next_if_eq(&NEGATED_CLASS_SYMBOL).is_some();
+    let mut items = Vec::new();
+    loop {
+        let char = chars.next().ok_or(unclosed_error.clone())?;
+        if char == CHARACTER_CLASS_END_SYMBOL && !items.is_empty() {
+            break;
+        }
+        let item_column = column(chars);
+        if char == POSIX_CLASS_START_SYMBOL {
+            if let Some(name) = parse_posix_class_name(chars) {
+                let posix_class =
+                    PosixClass::from_name(&name).ok_or(PatternError::UnknownPosixClass {
+                        column: item_column,
+                    })?;
+                items.push(ClassItem::Posix(posix_class));
+                continue;
+            }
         }
+        if char == ESCAPE_SYMBOL {
+            if let Some((property, is_negated)) = parse_unicode_property(chars, item_column)? {
+                items.push(ClassItem::UnicodeProperty {
+                    property,
+                    is_negated,
+                });
+                continue;
+            }
+        }
+        let Some(item) = parse_class_item(char, chars) else {
+            return Err(unclosed_error);
+        };
+        let ClassItem::Char(start) = item else {
+            items.push(item);
+            continue;
+        };
 
-        // Wildcard
-        if char == WILDCARD_SYMBOL {
-            patterns.push(Pattern::Wildcard);
+        let mut range_chars = chars.clone();
+        let is_range = range_chars.next() == Some(CLASS_RANGE_SYMBOL)
+            && range_chars
+                .peek()
+                .is_some_and(|&c| c != CHARACTER_CLASS_END_SYMBOL);
+        if !is_range {
+            items.push(item);
             continue;
         }
+        *chars = range_chars;
+        let end_char = chars.next().ok_or(unclosed_error.clone())?;
+        match parse_class_item(end_char, chars) {
+            Some(ClassItem::Char(end)) if start <= end => items.push(ClassItem::Range(start, end)),
+            Some(_) => {
+                return Err(PatternError::InvalidClassRange {
+                    column: item_column,
+                })
+            }
+            None => return Err(unclosed_error),
+        }
+    }
+
+    Ok(CharacterClass { is_negated, items })
+}
+
+// Parses a char of a character class, escaped or not, unless it's an incomplete escape sequence
+fn parse_class_item(char: char, chars: &mut Peekable<Chars>) -> Option<ClassItem> {
+    if char != ESCAPE_SYMBOL {
+        return Some(ClassItem::Char(char));
+    }
+    let item = match chars.next()? {
+        DIGIT_SYMBOL => ClassItem::Digit,
+        ALPHANUMERIC_SYMBOL => ClassItem::Alphanumeric,
+        WHITESPACE_SYMBOL => ClassItem::Whitespace,
+        NON_DIGIT_SYMBOL => ClassItem::NonDigit,
+        NON_ALPHANUMERIC_SYMBOL => ClassItem::NonAlphanumeric,
+        NON_WHITESPACE_SYMBOL => ClassItem::NonWhitespace,
+        c => ClassItem::Char(c),
+    };
+    Some(item)
+}
+
+// Parses the "p{name}" or "P{name}" of a Unicode property following a "\", or "pL" with a single
+// letter name, advancing the chars only if it's one
+fn parse_unicode_property(
+    chars: &mut Peekable<Chars>,
+    column: usize,
+) -> Result<Option<(Property, bool)>, PatternError> {
+    let is_negated = match chars.peek() {
+        Some(&UNICODE_PROPERTY_SYMBOL) => false,
+        Some(&NEGATED_UNICODE_PROPERTY_SYMBOL) => true,
+        _ => return Ok(None),
+    };
+    chars.next();
+    let name = match chars.next() {
+        Some(UNICODE_PROPERTY_START_SYMBOL) => {
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some(UNICODE_PROPERTY_END_SYMBOL) => break Some(name),
+                    Some(c) => name.push(c),
+                    None => break None,
+                }
+            }
+        }
+        c => c.map(String::from),
+    };
+    let property = name
+        .and_then(|name| Property::from_name(&name))
+        .ok_or(PatternError::UnknownUnicodeProperty { column })?;
+    Ok(Some((property, is_negated)))
+}
 
-        // Literal
-        patterns.push(Pattern::Literal(char));
+// Parses the "name:]" of a "[:name:]" following its "[", advancing the chars only if it's
+// complete. Otherwise the "[" is a literal
+fn parse_posix_class_name(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut name_chars = chars.clone();
+    if name_chars.next()? != POSIX_CLASS_DELIMITER_SYMBOL {
+        return None;
     }
+    let mut name = String::new();
+    loop {
+        match name_chars.next()? {
+            POSIX_CLASS_DELIMITER_SYMBOL
+                if name_chars.peek() == Some(&CHARACTER_CLASS_END_SYMBOL) =>
+            {
+                name_chars.next();
+                break;
+            }
+            CHARACTER_CLASS_END_SYMBOL => return None,
+            c => name.push(c),
+        }
+    }
+    *chars = name_chars;
+    Some(name)
+}
+
+// Parses the "imsx-imsx" flags following a "(?", set before the "-" and unset after it, up to
+// the ")" or ":" that is returned with the flags, unless there are none
+fn parse_flags(chars: &mut Peekable<Chars>, flags: Flags) -> Option<(Flags, char)> {
+    let mut flags = flags;
+    let mut is_set = true;
+    let mut is_any_flag = false;
+    loop {
+        let flag = match chars.next()? {
+            CASE_INSENSITIVE_FLAG => &mut flags.is_case_insensitive,
+            MULTILINE_FLAG => &mut flags.is_multiline,
+            DOT_ALL_FLAG => &mut flags.is_dot_all,
+            EXTENDED_FLAG => &mut flags.is_extended,
+            NEGATED_FLAGS_SYMBOL if is_set => {
+                is_set = false;
+                continue;
+            }
+            c @ (CAPTURING_GROUP_END_SYMBOL | NON_CAPTURING_GROUP_SYMBOL) if is_any_flag => {
+                return Some((flags, c));
+            }
+            _ => return None,
+        };
+        *flag = is_set;
+        is_any_flag = true;
+    }
+}
+
+// Parses the "n}", "n,}" or "n,m}" following a "{", advancing the chars only if it's valid
+fn parse_repeat_range(chars: &mut Peekable<Chars>) -> Option<(usize, Option<usize>)> {
+    let mut range_chars = chars.clone();
+    let mut range_string = String::new();
+    loop {
+        match range_chars.next()? {
+            REPEAT_END_SYMBOL => break,
+            c => range_string.push(c),
+        }
+    }
+
+    let parse_count = |count_string: &str| {
+        if count_string.is_empty() || !count_string.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        count_string.parse::<usize>().ok()
+    };
+    let range = match range_string.split_once(REPEAT_SEPARATOR_SYMBOL) {
+        None => {
+            let count = parse_count(&range_string)?;
+            (count, Some(count))
+        }
+        Some((min_string, "")) => (parse_count(min_string)?, None),
+        Some((min_string, max_string)) => {
+            (parse_count(min_string)?, Some(parse_count(max_string)?))
+        }
+    };
 
-    patterns
+    *chars = range_chars;
+    Some(range)
 }
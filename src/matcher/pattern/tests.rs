@@ -2,14 +2,24 @@
 mod tests {
     use std::vec;
 
-    use crate::matcher::pattern::{parse_pattern, Pattern};
+    use crate::matcher::pattern::{
+        parse_pattern, CharacterClass, ClassItem, Pattern, PatternError, PosixClass,
+    };
+    use crate::matcher::unicode::Property;
+
+    fn chars_class(is_negated: bool, chars: &str) -> Pattern {
+        Pattern::CharacterClass(CharacterClass {
+            is_negated,
+            items: chars.chars().map(ClassItem::Char).collect(),
+        })
+    }
 
     #[test]
     fn test_parse_pattern_with_literal() {
-        assert_eq!(parse_pattern("h"), vec![Pattern::Literal('h')]);
-        assert_eq!(parse_pattern("z"), vec![Pattern::Literal('z')]);
+        assert_eq!(parse_pattern("h").unwrap(), vec![Pattern::Literal('h')]);
+        assert_eq!(parse_pattern("z").unwrap(), vec![Pattern::Literal('z')]);
         assert_eq!(
-            parse_pattern("zoz"),
+            parse_pattern("zoz").unwrap(),
             vec![
                 Pattern::Literal('z'),
                 Pattern::Literal('o'),
@@ -20,83 +30,321 @@ mod tests {
 
     #[test]
     fn test_parse_pattern_with_digit() {
-        assert_eq!(parse_pattern(r"\d"), vec![Pattern::Digit]);
-        assert_eq!(parse_pattern(r"\d\d"), vec![Pattern::Digit, Pattern::Digit]);
+        assert_eq!(parse_pattern(r"\d").unwrap(), vec![Pattern::Digit]);
+        assert_eq!(
+            parse_pattern(r"\d\d").unwrap(),
+            vec![Pattern::Digit, Pattern::Digit]
+        );
     }
 
     #[test]
     fn test_parse_pattern_with_alphanumeric() {
-        assert_eq!(parse_pattern(r"\w"), vec![Pattern::Alphanumeric]);
+        assert_eq!(parse_pattern(r"\w").unwrap(), vec![Pattern::Alphanumeric]);
         assert_eq!(
-            parse_pattern(r"\w\w"),
+            parse_pattern(r"\w\w").unwrap(),
             vec![Pattern::Alphanumeric, Pattern::Alphanumeric]
         );
     }
 
     #[test]
-    fn test_parse_pattern_with_positive_group() {
+    fn test_parse_pattern_with_shorthand_classes() {
+        assert_eq!(
+            parse_pattern(r"\s\S\D\W").unwrap(),
+            vec![
+                Pattern::Whitespace,
+                Pattern::NonWhitespace,
+                Pattern::NonDigit,
+                Pattern::NonAlphanumeric
+            ]
+        );
+        assert_eq!(
+            parse_pattern(r"[\S\D\W]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: false,
+                items: vec![
+                    ClassItem::NonWhitespace,
+                    ClassItem::NonDigit,
+                    ClassItem::NonAlphanumeric
+                ]
+            })]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_word_boundary() {
+        assert_eq!(
+            parse_pattern(r"\bcat\B").unwrap(),
+            vec![
+                Pattern::WordBoundary,
+                Pattern::Literal('c'),
+                Pattern::Literal('a'),
+                Pattern::Literal('t'),
+                Pattern::NonWordBoundary
+            ]
+        );
+        // A boundary inside a class is the letter
         assert_eq!(
-            parse_pattern("[a]"),
-            vec![Pattern::PositiveGroup("a".to_string())]
+            parse_pattern(r"[\b]").unwrap(),
+            vec![chars_class(false, "b")]
         );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_positive_group() {
+        assert_eq!(parse_pattern("[a]").unwrap(), vec![chars_class(false, "a")]);
         assert_eq!(
-            parse_pattern("[abc]"),
-            vec![Pattern::PositiveGroup("abc".to_string())]
+            parse_pattern("[abc]").unwrap(),
+            vec![chars_class(false, "abc")]
         );
     }
 
     #[test]
     fn test_parse_pattern_with_negative_group() {
+        assert_eq!(parse_pattern("[^a]").unwrap(), vec![chars_class(true, "a")]);
+        assert_eq!(
+            parse_pattern("[^abc]").unwrap(),
+            vec![chars_class(true, "abc")]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_character_class() {
         assert_eq!(
-            parse_pattern("[^a]"),
-            vec![Pattern::NegativeGroup("a".to_string())]
+            parse_pattern("[a-z0-9_]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: false,
+                items: vec![
+                    ClassItem::Range('a', 'z'),
+                    ClassItem::Range('0', '9'),
+                    ClassItem::Char('_')
+                ]
+            })]
         );
         assert_eq!(
-            parse_pattern("[^abc]"),
-            vec![Pattern::NegativeGroup("abc".to_string())]
+            parse_pattern(r"[^\d\w\s.-]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: true,
+                items: vec![
+                    ClassItem::Digit,
+                    ClassItem::Alphanumeric,
+                    ClassItem::Whitespace,
+                    ClassItem::Char('.'),
+                    ClassItem::Char('-')
+                ]
+            })]
+        );
+        assert_eq!(
+            parse_pattern(r"[\]\-a\-\\]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: false,
+                items: vec![
+                    ClassItem::Char(']'),
+                    ClassItem::Char('-'),
+                    ClassItem::Char('a'),
+                    ClassItem::Char('-'),
+                    ClassItem::Char('\\')
+                ]
+            })]
+        );
+        assert_eq!(
+            parse_pattern(r"[\--\]]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: false,
+                items: vec![ClassItem::Range('-', ']')]
+            })]
+        );
+        assert_eq!(
+            parse_pattern("[]a]").unwrap(),
+            vec![chars_class(false, "]a")]
+        );
+        assert_eq!(parse_pattern("[^]]").unwrap(), vec![chars_class(true, "]")]);
+        assert_eq!(
+            parse_pattern("[-a-]").unwrap(),
+            vec![chars_class(false, "-a-")]
+        );
+        assert_eq!(
+            parse_pattern("[a-]]").unwrap(),
+            vec![chars_class(false, "a-"), Pattern::Literal(']')]
+        );
+        assert_eq!(
+            parse_pattern("[é-ü]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: false,
+                items: vec![ClassItem::Range('é', 'ü')]
+            })]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_posix_class() {
+        assert_eq!(
+            parse_pattern("[[:alpha:]]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: false,
+                items: vec![ClassItem::Posix(PosixClass::Alpha)]
+            })]
+        );
+        assert_eq!(
+            parse_pattern("[^_[:space:][:xdigit:]-]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: true,
+                items: vec![
+                    ClassItem::Char('_'),
+                    ClassItem::Posix(PosixClass::Space),
+                    ClassItem::Posix(PosixClass::Xdigit),
+                    ClassItem::Char('-')
+                ]
+            })]
+        );
+        // Without the closing ":]" the "[" is a literal
+        assert_eq!(
+            parse_pattern("[[:a]").unwrap(),
+            vec![chars_class(false, "[:a")]
+        );
+        assert_eq!(
+            parse_pattern("[[a]]").unwrap(),
+            vec![chars_class(false, "[a"), Pattern::Literal(']')]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_unicode_property() {
+        let property = |name| Property::from_name(name).unwrap();
+        assert_eq!(
+            parse_pattern("\\p{Greek}\\PL\\p{gc=Nd}").unwrap(),
+            vec![
+                Pattern::UnicodeProperty {
+                    property: property("Greek"),
+                    is_negated: false
+                },
+                Pattern::UnicodeProperty {
+                    property: property("Letter"),
+                    is_negated: true
+                },
+                Pattern::UnicodeProperty {
+                    property: property("Decimal_Number"),
+                    is_negated: false
+                }
+            ]
+        );
+        assert_eq!(
+            parse_pattern("[^a\\P{Han}]").unwrap(),
+            vec![Pattern::CharacterClass(CharacterClass {
+                is_negated: true,
+                items: vec![
+                    ClassItem::Char('a'),
+                    ClassItem::UnicodeProperty {
+                        property: property("Han"),
+                        is_negated: true
+                    }
+                ]
+            })]
+        );
+        assert_eq!(
+            parse_pattern("a\\p{Klingon}"),
+            Err(PatternError::UnknownUnicodeProperty { column: 2 })
+        );
+        assert_eq!(
+            parse_pattern("[a\\p{L]"),
+            Err(PatternError::UnknownUnicodeProperty { column: 3 })
+        );
+        assert_eq!(
+            parse_pattern("\\p"),
+            Err(PatternError::UnknownUnicodeProperty { column: 1 })
         );
     }
 
     #[test]
     fn test_parse_pattern_with_start_of_string() {
         assert_eq!(
-            parse_pattern("^h"),
-            vec![Pattern::StartOfString(Box::new(Pattern::Literal('h')))]
+            parse_pattern("^h").unwrap(),
+            vec![Pattern::StartOfString, Pattern::Literal('h')]
         );
         assert_eq!(
-            parse_pattern("^abc"),
+            parse_pattern("^abc").unwrap(),
             vec![
-                Pattern::StartOfString(Box::new(Pattern::Literal('a'))),
+                Pattern::StartOfString,
+                Pattern::Literal('a'),
                 Pattern::Literal('b'),
                 Pattern::Literal('c')
             ]
         );
         assert_eq!(
-            parse_pattern("^(hey)"),
-            vec![Pattern::StartOfString(Box::new(Pattern::CapturingGroup(
-                vec![
+            parse_pattern("^(hey)").unwrap(),
+            vec![
+                Pattern::StartOfString,
+                Pattern::CapturingGroup(vec![
                     Pattern::Literal('h'),
                     Pattern::Literal('e'),
                     Pattern::Literal('y')
-                ]
-            )))]
+                ])
+            ]
         );
         assert_eq!(
-            parse_pattern("^(\\w+)"),
-            vec![Pattern::StartOfString(Box::new(Pattern::CapturingGroup(
-                vec![Pattern::OneOrMore(Box::new(Pattern::Alphanumeric))]
-            )))]
+            parse_pattern("^(\\w+)").unwrap(),
+            vec![
+                Pattern::StartOfString,
+                Pattern::CapturingGroup(vec![Pattern::Repeat {
+                    pattern: Box::new(Pattern::Alphanumeric),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                }])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_anchors_anywhere() {
+        assert_eq!(parse_pattern("^").unwrap(), vec![Pattern::StartOfString]);
+        assert_eq!(
+            parse_pattern("(^a|b$)").unwrap(),
+            vec![Pattern::CapturingGroup(vec![Pattern::Alternation(vec![
+                vec![Pattern::StartOfString, Pattern::Literal('a')],
+                vec![Pattern::Literal('b'), Pattern::EndOfString]
+            ])])]
+        );
+        assert_eq!(
+            parse_pattern("a$\\nb\\A").unwrap(),
+            vec![
+                Pattern::Literal('a'),
+                Pattern::EndOfString,
+                Pattern::Literal('n'),
+                Pattern::Literal('b'),
+                Pattern::StartOfString
+            ]
+        );
+        assert_eq!(
+            parse_pattern("\\Aa\\Z|\\z").unwrap(),
+            vec![Pattern::Alternation(vec![
+                vec![
+                    Pattern::StartOfString,
+                    Pattern::Literal('a'),
+                    Pattern::EndOfLastLine
+                ],
+                vec![Pattern::EndOfString]
+            ])]
+        );
+        // In the multiline mode "\A" and "\z" are still the start and end of the string
+        assert_eq!(
+            parse_pattern("(?m)\\A^$\\z").unwrap(),
+            vec![
+                Pattern::StartOfString,
+                Pattern::StartOfLine,
+                Pattern::EndOfLine,
+                Pattern::EndOfString
+            ]
         );
     }
 
     #[test]
     fn test_parse_pattern_with_end_of_string() {
         assert_eq!(
-            parse_pattern("h$"),
+            parse_pattern("h$").unwrap(),
             vec![Pattern::Literal('h'), Pattern::EndOfString]
         );
         assert_eq!(
-            parse_pattern("abc$"),
+            parse_pattern("abc$").unwrap(),
             vec![
                 Pattern::Literal('a'),
                 Pattern::Literal('b'),
@@ -105,7 +353,7 @@ mod tests {
             ]
         );
         assert_eq!(
-            parse_pattern("(\\w)$"),
+            parse_pattern("(\\w)$").unwrap(),
             vec![
                 Pattern::CapturingGroup(vec![Pattern::Alphanumeric]),
                 Pattern::EndOfString
@@ -116,32 +364,206 @@ mod tests {
     #[test]
     fn test_parse_pattern_with_zero_or_one() {
         assert_eq!(
-            parse_pattern("h?"),
-            vec![Pattern::ZeroOrOne(Box::new(Pattern::Literal('h')))]
+            parse_pattern("h?").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('h')),
+                min: 0,
+                max: Some(1),
+                is_lazy: false
+            }]
         );
         assert_eq!(
-            parse_pattern("A?"),
-            vec![Pattern::ZeroOrOne(Box::new(Pattern::Literal('A')))]
+            parse_pattern("A?").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('A')),
+                min: 0,
+                max: Some(1),
+                is_lazy: false
+            }]
         );
     }
 
     #[test]
     fn test_parse_pattern_with_one_or_more() {
         assert_eq!(
-            parse_pattern("h+"),
-            vec![Pattern::OneOrMore(Box::new(Pattern::Literal('h')))]
+            parse_pattern("h+").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('h')),
+                min: 1,
+                max: None,
+                is_lazy: false
+            }]
+        );
+        assert_eq!(
+            parse_pattern("A+").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('A')),
+                min: 1,
+                max: None,
+                is_lazy: false
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_zero_or_more() {
+        assert_eq!(
+            parse_pattern("A*").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('A')),
+                min: 0,
+                max: None,
+                is_lazy: false
+            }]
+        );
+        assert_eq!(
+            parse_pattern("a.*b").unwrap(),
+            vec![
+                Pattern::Literal('a'),
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Wildcard),
+                    min: 0,
+                    max: None,
+                    is_lazy: false
+                },
+                Pattern::Literal('b')
+            ]
+        );
+        assert_eq!(
+            parse_pattern("(ab)*").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::CapturingGroup(vec![
+                    Pattern::Literal('a'),
+                    Pattern::Literal('b')
+                ])),
+                min: 0,
+                max: None,
+                is_lazy: false
+            }]
         );
         assert_eq!(
-            parse_pattern("A+"),
-            vec![Pattern::OneOrMore(Box::new(Pattern::Literal('A')))]
+            parse_pattern("a\\*").unwrap(),
+            vec![Pattern::Literal('a'), Pattern::Literal('*')]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_repeat() {
+        assert_eq!(
+            parse_pattern("h{3}").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('h')),
+                min: 3,
+                max: Some(3),
+                is_lazy: false
+            }]
+        );
+        assert_eq!(
+            parse_pattern("\\d{2,}").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Digit),
+                min: 2,
+                max: None,
+                is_lazy: false
+            }]
+        );
+        assert_eq!(
+            parse_pattern("(ab){0,12}c").unwrap(),
+            vec![
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::CapturingGroup(vec![
+                        Pattern::Literal('a'),
+                        Pattern::Literal('b')
+                    ])),
+                    min: 0,
+                    max: Some(12),
+                    is_lazy: false
+                },
+                Pattern::Literal('c')
+            ]
+        );
+        assert_eq!(
+            parse_pattern("a{,2}").unwrap(),
+            vec![
+                Pattern::Literal('a'),
+                Pattern::Literal('{'),
+                Pattern::Literal(','),
+                Pattern::Literal('2'),
+                Pattern::Literal('}')
+            ]
+        );
+        assert_eq!(
+            parse_pattern("a{1").unwrap(),
+            vec![
+                Pattern::Literal('a'),
+                Pattern::Literal('{'),
+                Pattern::Literal('1')
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_lazy_repeat() {
+        assert_eq!(
+            parse_pattern("h+?").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('h')),
+                min: 1,
+                max: None,
+                is_lazy: true
+            }]
+        );
+        assert_eq!(
+            parse_pattern("h??").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('h')),
+                min: 0,
+                max: Some(1),
+                is_lazy: true
+            }]
+        );
+        assert_eq!(
+            parse_pattern(".*?x").unwrap(),
+            vec![
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Wildcard),
+                    min: 0,
+                    max: None,
+                    is_lazy: true
+                },
+                Pattern::Literal('x')
+            ]
+        );
+        assert_eq!(
+            parse_pattern("h{2,5}?").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Literal('h')),
+                min: 2,
+                max: Some(5),
+                is_lazy: true
+            }]
+        );
+        assert_eq!(
+            parse_pattern("h+??").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('h')),
+                    min: 1,
+                    max: None,
+                    is_lazy: true
+                }),
+                min: 0,
+                max: Some(1),
+                is_lazy: false
+            }]
         );
     }
 
     #[test]
     fn test_parse_pattern_with_wildcard() {
-        assert_eq!(parse_pattern("."), vec![Pattern::Wildcard]);
+        assert_eq!(parse_pattern(".").unwrap(), vec![Pattern::Wildcard]);
         assert_eq!(
-            parse_pattern("d.g.o"),
+            parse_pattern("d.g.o").unwrap(),
             vec![
                 Pattern::Literal('d'),
                 Pattern::Wildcard,
@@ -155,11 +577,11 @@ mod tests {
     #[test]
     fn test_parse_pattern_with_capturing_group() {
         assert_eq!(
-            parse_pattern("(a)"),
+            parse_pattern("(a)").unwrap(),
             vec![Pattern::CapturingGroup(vec![Pattern::Literal('a')])]
         );
         assert_eq!(
-            parse_pattern("(c\\d)"),
+            parse_pattern("(c\\d)").unwrap(),
             vec![Pattern::CapturingGroup(vec![
                 Pattern::Literal('c'),
                 Pattern::Digit
@@ -170,18 +592,64 @@ mod tests {
     #[test]
     fn test_parse_pattern_with_alternation() {
         assert_eq!(
-            parse_pattern("(a|b)"),
-            vec![Pattern::Alternation(vec![
+            parse_pattern("(a|b)").unwrap(),
+            vec![Pattern::CapturingGroup(vec![Pattern::Alternation(vec![
                 vec![Pattern::Literal('a')],
                 vec![Pattern::Literal('b')]
-            ])]
+            ])])]
         );
         assert_eq!(
-            parse_pattern("(a|b|cc)"),
-            vec![Pattern::Alternation(vec![
+            parse_pattern("(a|b|cc)").unwrap(),
+            vec![Pattern::CapturingGroup(vec![Pattern::Alternation(vec![
                 vec![Pattern::Literal('a')],
                 vec![Pattern::Literal('b')],
                 vec![Pattern::Literal('c'), Pattern::Literal('c')]
+            ])])]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_nested_groups() {
+        assert_eq!(
+            parse_pattern("((a|b)c)+").unwrap(),
+            vec![Pattern::Repeat {
+                pattern: Box::new(Pattern::CapturingGroup(vec![
+                    Pattern::CapturingGroup(vec![Pattern::Alternation(vec![
+                        vec![Pattern::Literal('a')],
+                        vec![Pattern::Literal('b')]
+                    ])]),
+                    Pattern::Literal('c')
+                ])),
+                min: 1,
+                max: None,
+                is_lazy: false
+            }]
+        );
+        assert_eq!(
+            parse_pattern("ab|(c|)|").unwrap(),
+            vec![Pattern::Alternation(vec![
+                vec![Pattern::Literal('a'), Pattern::Literal('b')],
+                vec![Pattern::CapturingGroup(vec![Pattern::Alternation(vec![
+                    vec![Pattern::Literal('c')],
+                    vec![]
+                ])])],
+                vec![]
+            ])]
+        );
+        // A "|" in brackets is a char of the class, and a ")" closing no group is a literal
+        assert_eq!(
+            parse_pattern("([|)])").unwrap(),
+            vec![Pattern::CapturingGroup(vec![chars_class(false, "|)")])]
+        );
+        assert_eq!(
+            parse_pattern("a)").unwrap(),
+            vec![Pattern::Literal('a'), Pattern::Literal(')')]
+        );
+        assert_eq!(
+            parse_pattern("^a|b$").unwrap(),
+            vec![Pattern::Alternation(vec![
+                vec![Pattern::StartOfString, Pattern::Literal('a')],
+                vec![Pattern::Literal('b'), Pattern::EndOfString]
             ])]
         );
     }
@@ -189,7 +657,7 @@ mod tests {
     #[test]
     fn test_parse_pattern_with_backreference() {
         assert_eq!(
-            parse_pattern("(a) \\1.(b) \\2"),
+            parse_pattern("(a) \\1.(b) \\2").unwrap(),
             vec![
                 Pattern::CapturingGroup(vec![Pattern::Literal('a')]),
                 Pattern::Literal(' '),
@@ -200,53 +668,157 @@ mod tests {
                 Pattern::Backreference(2)
             ]
         );
+        // A backreference can come before its group, but the group has to be in the pattern
+        assert_eq!(
+            parse_pattern("(\\2|b)(a)").unwrap(),
+            vec![
+                Pattern::CapturingGroup(vec![Pattern::Alternation(vec![
+                    vec![Pattern::Backreference(2)],
+                    vec![Pattern::Literal('b')]
+                ])]),
+                Pattern::CapturingGroup(vec![Pattern::Literal('a')])
+            ]
+        );
         assert_eq!(
             parse_pattern("(a|b).\\1.\\2"),
+            Err(PatternError::InvalidBackreference { column: 10 })
+        );
+        assert_eq!(
+            parse_pattern("\\1"),
+            Err(PatternError::InvalidBackreference { column: 1 })
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_non_capturing_and_named_groups() {
+        assert_eq!(
+            parse_pattern("(?:a|b)(?<x>c)(?P<y_2>d)\\k<y_2>\\2").unwrap(),
             vec![
-                Pattern::Alternation(vec![
+                Pattern::NonCapturingGroup(vec![Pattern::Alternation(vec![
                     vec![Pattern::Literal('a')],
                     vec![Pattern::Literal('b')]
-                ]),
-                Pattern::Wildcard,
-                Pattern::Backreference(1),
-                Pattern::Wildcard,
+                ])]),
+                Pattern::NamedCapturingGroup("x".to_string(), vec![Pattern::Literal('c')]),
+                Pattern::NamedCapturingGroup("y_2".to_string(), vec![Pattern::Literal('d')]),
+                Pattern::Backreference(2),
                 Pattern::Backreference(2)
             ]
         );
+        // Without a "<" the "k" is a literal
+        assert_eq!(parse_pattern("\\k").unwrap(), vec![Pattern::Literal('k')]);
     }
 
     #[test]
-    fn test_parse_pattern_with_combinations_of_patterns() {
+    fn test_parse_pattern_with_lookarounds() {
         assert_eq!(
-            parse_pattern("[a][b]"),
+            parse_pattern("(?=a)(?!b)(?<=c)(?<!(d))").unwrap(),
             vec![
-                Pattern::PositiveGroup("a".to_string()),
-                Pattern::PositiveGroup("b".to_string())
+                Pattern::Lookahead {
+                    patterns: vec![Pattern::Literal('a')],
+                    is_negated: false
+                },
+                Pattern::Lookahead {
+                    patterns: vec![Pattern::Literal('b')],
+                    is_negated: true
+                },
+                Pattern::Lookbehind {
+                    patterns: vec![Pattern::Literal('c')],
+                    is_negated: false
+                },
+                Pattern::Lookbehind {
+                    patterns: vec![Pattern::CapturingGroup(vec![Pattern::Literal('d')])],
+                    is_negated: true
+                }
             ]
         );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_flags() {
+        let case_insensitive = |c| Pattern::CaseInsensitive(Box::new(Pattern::Literal(c)));
         assert_eq!(
-            parse_pattern("[a]b"),
+            parse_pattern("(?i)a1(?-i)b").unwrap(),
             vec![
-                Pattern::PositiveGroup("a".to_string()),
+                case_insensitive('a'),
+                Pattern::Literal('1'),
                 Pattern::Literal('b')
             ]
         );
+        // The flags set in a group are only for the group
         assert_eq!(
-            parse_pattern("a[bc]"),
+            parse_pattern("(a(?i)b)c(?i:d)e").unwrap(),
             vec![
-                Pattern::Literal('a'),
-                Pattern::PositiveGroup("bc".to_string())
+                Pattern::CapturingGroup(vec![Pattern::Literal('a'), case_insensitive('b')]),
+                Pattern::Literal('c'),
+                Pattern::NonCapturingGroup(vec![case_insensitive('d')]),
+                Pattern::Literal('e')
+            ]
+        );
+        assert_eq!(
+            parse_pattern("(?ms)^.$").unwrap(),
+            vec![Pattern::StartOfLine, Pattern::AnyChar, Pattern::EndOfLine]
+        );
+        assert_eq!(
+            parse_pattern("(?x) a + # comment\n | [ ]\\ ").unwrap(),
+            vec![Pattern::Alternation(vec![
+                vec![Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('a')),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                }],
+                vec![chars_class(false, " "), Pattern::Literal(' ')]
+            ])]
+        );
+        // Without a valid flag it's a group starting with a "?"
+        assert_eq!(
+            parse_pattern("(?y)"),
+            Err(PatternError::NothingToRepeat { column: 2 })
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_multi_digit_backreference() {
+        let groups = "(a)".repeat(12);
+        let patterns = parse_pattern(&format!("{groups}\\12\\123")).unwrap();
+        assert_eq!(
+            patterns[12..],
+            [
+                Pattern::Backreference(12),
+                Pattern::Backreference(12),
+                Pattern::Literal('3')
             ]
         );
         assert_eq!(
-            parse_pattern("a[^bc]"),
+            parse_pattern("(a)\\10").unwrap(),
             vec![
-                Pattern::Literal('a'),
-                Pattern::NegativeGroup("bc".to_string())
+                Pattern::CapturingGroup(vec![Pattern::Literal('a')]),
+                Pattern::Backreference(1),
+                Pattern::Literal('0')
             ]
         );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_combinations_of_patterns() {
+        assert_eq!(
+            parse_pattern("[a][b]").unwrap(),
+            vec![chars_class(false, "a"), chars_class(false, "b")]
+        );
         assert_eq!(
-            parse_pattern(r"\d\d\ds"),
+            parse_pattern("[a]b").unwrap(),
+            vec![chars_class(false, "a"), Pattern::Literal('b')]
+        );
+        assert_eq!(
+            parse_pattern("a[bc]").unwrap(),
+            vec![Pattern::Literal('a'), chars_class(false, "bc")]
+        );
+        assert_eq!(
+            parse_pattern("a[^bc]").unwrap(),
+            vec![Pattern::Literal('a'), chars_class(true, "bc")]
+        );
+        assert_eq!(
+            parse_pattern(r"\d\d\ds").unwrap(),
             vec![
                 Pattern::Digit,
                 Pattern::Digit,
@@ -255,7 +827,7 @@ mod tests {
             ]
         );
         assert_eq!(
-            parse_pattern(r"\d\w\dxxx"),
+            parse_pattern(r"\d\w\dxxx").unwrap(),
             vec![
                 Pattern::Digit,
                 Pattern::Alphanumeric,
@@ -266,58 +838,93 @@ mod tests {
             ]
         );
         assert_eq!(
-            parse_pattern("ab?c"),
+            parse_pattern("ab?c").unwrap(),
             vec![
                 Pattern::Literal('a'),
-                Pattern::ZeroOrOne(Box::new(Pattern::Literal('b'))),
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('b')),
+                    min: 0,
+                    max: Some(1),
+                    is_lazy: false
+                },
                 Pattern::Literal('c')
             ]
         );
         assert_eq!(
-            parse_pattern("hey?"),
+            parse_pattern("hey?").unwrap(),
             vec![
                 Pattern::Literal('h'),
                 Pattern::Literal('e'),
-                Pattern::ZeroOrOne(Box::new(Pattern::Literal('y')))
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('y')),
+                    min: 0,
+                    max: Some(1),
+                    is_lazy: false
+                }
             ]
         );
         assert_eq!(
-            parse_pattern("ab+c"),
+            parse_pattern("ab+c").unwrap(),
             vec![
                 Pattern::Literal('a'),
-                Pattern::OneOrMore(Box::new(Pattern::Literal('b'))),
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('b')),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                },
                 Pattern::Literal('c')
             ]
         );
         assert_eq!(
-            parse_pattern("hey+"),
+            parse_pattern("hey+").unwrap(),
             vec![
                 Pattern::Literal('h'),
                 Pattern::Literal('e'),
-                Pattern::OneOrMore(Box::new(Pattern::Literal('y')))
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('y')),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                }
             ]
         );
         assert_eq!(
-            parse_pattern("h?e.y+"),
+            parse_pattern("h?e.y+").unwrap(),
             vec![
-                Pattern::ZeroOrOne(Box::new(Pattern::Literal('h'))),
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('h')),
+                    min: 0,
+                    max: Some(1),
+                    is_lazy: false
+                },
                 Pattern::Literal('e'),
                 Pattern::Wildcard,
-                Pattern::OneOrMore(Box::new(Pattern::Literal('y')))
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('y')),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                }
             ]
         );
         assert_eq!(
-            parse_pattern("(c?**\\w)"),
+            parse_pattern("(c?\\*\\*\\w)").unwrap(),
             vec![Pattern::CapturingGroup(vec![
-                Pattern::ZeroOrOne(Box::new(Pattern::Literal('c'))),
+                Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('c')),
+                    min: 0,
+                    max: Some(1),
+                    is_lazy: false
+                },
                 Pattern::Literal('*'),
                 Pattern::Literal('*'),
                 Pattern::Alphanumeric
             ])]
         );
         assert_eq!(
-            parse_pattern("(dog|.ss|f?i+)"),
-            vec![Pattern::Alternation(vec![
+            parse_pattern("(dog|.ss|f?i+)").unwrap(),
+            vec![Pattern::CapturingGroup(vec![Pattern::Alternation(vec![
                 vec![
                     Pattern::Literal('d'),
                     Pattern::Literal('o'),
@@ -329,25 +936,40 @@ mod tests {
                     Pattern::Literal('s')
                 ],
                 vec![
-                    Pattern::ZeroOrOne(Box::new(Pattern::Literal('f'))),
-                    Pattern::OneOrMore(Box::new(Pattern::Literal('i')))
+                    Pattern::Repeat {
+                        pattern: Box::new(Pattern::Literal('f')),
+                        min: 0,
+                        max: Some(1),
+                        is_lazy: false
+                    },
+                    Pattern::Repeat {
+                        pattern: Box::new(Pattern::Literal('i')),
+                        min: 1,
+                        max: None,
+                        is_lazy: false
+                    }
                 ]
-            ])]
+            ])])]
         );
         assert_eq!(
-            parse_pattern("(.a)_\\1.(b+)_\\2"),
+            parse_pattern("(.a)_\\1.(b+)_\\2").unwrap(),
             vec![
                 Pattern::CapturingGroup(vec![Pattern::Wildcard, Pattern::Literal('a')]),
                 Pattern::Literal('_'),
                 Pattern::Backreference(1),
                 Pattern::Wildcard,
-                Pattern::CapturingGroup(vec![Pattern::OneOrMore(Box::new(Pattern::Literal('b')))]),
+                Pattern::CapturingGroup(vec![Pattern::Repeat {
+                    pattern: Box::new(Pattern::Literal('b')),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                }]),
                 Pattern::Literal('_'),
                 Pattern::Backreference(2)
             ]
         );
         assert_eq!(
-            parse_pattern("(\\w\\w\\w\\w \\d\\d\\d) is doing \\1 times"),
+            parse_pattern("(\\w\\w\\w\\w \\d\\d\\d) is doing \\1 times").unwrap(),
             vec![
                 Pattern::CapturingGroup(vec![
                     Pattern::Alphanumeric,
@@ -379,11 +1001,14 @@ mod tests {
             ]
         );
         assert_eq!(
-            parse_pattern("([abcd]+) is \\1, not [^xyz]+"),
+            parse_pattern("([abcd]+) is \\1, not [^xyz]+").unwrap(),
             vec![
-                Pattern::CapturingGroup(vec![Pattern::OneOrMore(Box::new(
-                    Pattern::PositiveGroup("abcd".to_string())
-                ))]),
+                Pattern::CapturingGroup(vec![Pattern::Repeat {
+                    pattern: Box::new(chars_class(false, "abcd")),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                }]),
                 Pattern::Literal(' '),
                 Pattern::Literal('i'),
                 Pattern::Literal('s'),
@@ -395,15 +1020,24 @@ mod tests {
                 Pattern::Literal('o'),
                 Pattern::Literal('t'),
                 Pattern::Literal(' '),
-                Pattern::OneOrMore(Box::new(Pattern::NegativeGroup("xyz".to_string())))
+                Pattern::Repeat {
+                    pattern: Box::new(chars_class(true, "xyz")),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                }
             ]
         );
         assert_eq!(
-            parse_pattern("^(\\w+) and \\1$"),
+            parse_pattern("^(\\w+) and \\1$").unwrap(),
             vec![
-                Pattern::StartOfString(Box::new(Pattern::CapturingGroup(vec![
-                    Pattern::OneOrMore(Box::new(Pattern::Alphanumeric))
-                ]))),
+                Pattern::StartOfString,
+                Pattern::CapturingGroup(vec![Pattern::Repeat {
+                    pattern: Box::new(Pattern::Alphanumeric),
+                    min: 1,
+                    max: None,
+                    is_lazy: false
+                }]),
                 Pattern::Literal(' '),
                 Pattern::Literal('a'),
                 Pattern::Literal('n'),
@@ -414,4 +1048,105 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_parse_pattern_with_errors() {
+        assert_eq!(
+            parse_pattern("[abc"),
+            Err(PatternError::UnclosedCharacterGroup { column: 1 })
+        );
+        assert_eq!(
+            parse_pattern("ab(cd"),
+            Err(PatternError::UnclosedCapturingGroup { column: 3 })
+        );
+        assert_eq!(
+            parse_pattern("ab\\"),
+            Err(PatternError::IncompleteEscapeSequence { column: 3 })
+        );
+        assert_eq!(
+            parse_pattern("(a)\\2"),
+            Err(PatternError::InvalidBackreference { column: 4 })
+        );
+        assert_eq!(
+            parse_pattern("+a"),
+            Err(PatternError::NothingToRepeat { column: 1 })
+        );
+        assert_eq!(
+            parse_pattern("^?"),
+            Err(PatternError::NothingToRepeat { column: 2 })
+        );
+        assert_eq!(
+            parse_pattern("(dog|?)"),
+            Err(PatternError::NothingToRepeat { column: 6 })
+        );
+        assert_eq!(
+            parse_pattern("(?a)"),
+            Err(PatternError::NothingToRepeat { column: 2 })
+        );
+        assert_eq!(
+            parse_pattern("(?<1a>b)"),
+            Err(PatternError::InvalidGroupName { column: 4 })
+        );
+        assert_eq!(
+            parse_pattern("(?P<a"),
+            Err(PatternError::InvalidGroupName { column: 5 })
+        );
+        assert_eq!(
+            parse_pattern("(?<a>b)(?<a>c)"),
+            Err(PatternError::DuplicateGroupName { column: 11 })
+        );
+        assert_eq!(
+            parse_pattern("(?<a>b)\\k<b>"),
+            Err(PatternError::UnknownGroupName { column: 11 })
+        );
+        assert_eq!(
+            parse_pattern("a(?<!b|c*)"),
+            Err(PatternError::UnboundedLookbehind { column: 2 })
+        );
+        assert_eq!(
+            parse_pattern("(a)(?<=\\1)"),
+            Err(PatternError::UnboundedLookbehind { column: 4 })
+        );
+        assert_eq!(
+            parse_pattern("((a)|b"),
+            Err(PatternError::UnclosedCapturingGroup { column: 1 })
+        );
+        assert_eq!(
+            parse_pattern("{2}"),
+            Err(PatternError::NothingToRepeat { column: 1 })
+        );
+        assert_eq!(
+            parse_pattern("ab{3,2}"),
+            Err(PatternError::InvalidRepeatRange { column: 3 })
+        );
+        assert_eq!(
+            parse_pattern("*a"),
+            Err(PatternError::NothingToRepeat { column: 1 })
+        );
+        assert_eq!(parse_pattern("é[ü").unwrap_err().column(), 2);
+        assert_eq!(
+            parse_pattern("[]"),
+            Err(PatternError::UnclosedCharacterGroup { column: 1 })
+        );
+        assert_eq!(
+            parse_pattern("a[b\\"),
+            Err(PatternError::UnclosedCharacterGroup { column: 2 })
+        );
+        assert_eq!(
+            parse_pattern("a[bz-a]"),
+            Err(PatternError::InvalidClassRange { column: 4 })
+        );
+        assert_eq!(
+            parse_pattern("[a-\\d]"),
+            Err(PatternError::InvalidClassRange { column: 2 })
+        );
+        assert_eq!(
+            parse_pattern("a[b[:word:]]"),
+            Err(PatternError::UnknownPosixClass { column: 4 })
+        );
+        assert_eq!(
+            parse_pattern("[[:alpha:]"),
+            Err(PatternError::UnclosedCharacterGroup { column: 1 })
+        );
+    }
 }
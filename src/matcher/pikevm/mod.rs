@@ -0,0 +1,257 @@
+use super::program::{Instruction, Program};
+use super::{is_matching_char, literal::Literals, Captures, Input};
+
+#[allow(clippy::module_inception)]
+mod tests;
+
+// Runs all the threads of the program in lockstep over the input, one char at a time, so the time
+// taken is linear in the input length whatever the pattern. Only the groups of the first
+// `slots_count` slots are recorded, the other ones being left out of the captures
+pub fn captures_at<'a>(
+    program: &Program,
+    slots_count: usize,
+    cache: &mut Cache,
+    literals: &Literals,
+    input: Input<'a>,
+    position: usize,
+) -> Option<Captures<'a>> {
+    cache.reset(program, slots_count);
+    let Cache {
+        current_threads,
+        next_threads,
+        slots,
+        stack,
+    } = cache;
+    let mut matched_slots = None;
+    let mut at = position;
+    loop {
+        // Without threads left, the next one starts where the prefix does
+        if current_threads.is_empty() && matched_slots.is_none() && !program.is_anchored {
+            at = literals.find_prefix(input.bytes, at)?;
+        }
+        // A new thread starts at each position until a match is found, with the lowest priority
+        // so that the match starting the leftmost wins
+        if matched_slots.is_none() && (at == position || !program.is_anchored) {
+            slots.fill(None);
+            let mut closure = Closure {
+                program,
+                input,
+                at,
+                slots,
+                stack,
+            };
+            closure.add_thread(current_threads, 0);
+        }
+        if current_threads.is_empty() {
+            break;
+        }
+
+        let next_char = input.next_char(at);
+        for index in 0..current_threads.len() {
+            let pc = current_threads.pcs[index];
+            match &program.instructions[pc] {
+                Instruction::Char(pattern) => {
+                    let Some((_, char_length)) = next_char.filter(|(char, _)| {
+                        char.is_some_and(|char| is_matching_char(pattern, char, input.mode))
+                    }) else {
+                        continue;
+                    };
+                    slots.copy_from_slice(current_threads.slots(pc));
+                    let mut closure = Closure {
+                        program,
+                        input,
+                        at: at + char_length,
+                        slots,
+                        stack,
+                    };
+                    closure.add_thread(next_threads, pc + 1);
+                }
+                Instruction::Match => {
+                    // The threads after this one have a lower priority, so they are dropped
+                    matched_slots = Some(current_threads.slots(pc).to_vec());
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let Some((_, char_length)) = next_char else {
+            break;
+        };
+        at += char_length;
+        std::mem::swap(current_threads, next_threads);
+        next_threads.clear();
+    }
+
+    let matched_slots = matched_slots?;
+    Some(Captures::from_slots(input.bytes, &matched_slots))
+}
+
+// The thread sets and buffers are kept for the next inputs, so that matching doesn't allocate
+// them again for every input
+#[derive(Default)]
+pub struct Cache {
+    current_threads: Threads,
+    next_threads: Threads,
+    slots: Vec<Option<usize>>,
+    stack: Vec<Frame>,
+}
+
+impl Cache {
+    // Empties the thread sets, sizing them for the program and the slots the first time
+    fn reset(&mut self, program: &Program, slots_count: usize) {
+        if self.slots.len() != slots_count
+            || self.current_threads.indexes.len() != program.instructions.len()
+        {
+            self.current_threads = Threads::new(program, slots_count);
+            self.next_threads = Threads::new(program, slots_count);
+            self.slots = vec![None; slots_count];
+        }
+        self.current_threads.clear();
+        self.next_threads.clear();
+        self.stack.clear();
+    }
+}
+
+// The threads at a position, as a sparse set of instruction indexes in order of priority, with
+// the slots recorded by each thread
+#[derive(Default)]
+struct Threads {
+    pcs: Vec<usize>,
+    indexes: Vec<usize>,
+    slots: Vec<Option<usize>>,
+    slots_count: usize,
+}
+
+impl Threads {
+    fn new(program: &Program, slots_count: usize) -> Self {
+        Threads {
+            pcs: Vec::with_capacity(program.instructions.len()),
+            indexes: vec![0; program.instructions.len()],
+            slots: vec![None; program.instructions.len() * slots_count],
+            slots_count,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.pcs.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pcs.is_empty()
+    }
+
+    fn contains(&self, pc: usize) -> bool {
+        self.pcs.get(self.indexes[pc]) == Some(&pc)
+    }
+
+    fn insert(&mut self, pc: usize) {
+        self.indexes[pc] = self.pcs.len();
+        self.pcs.push(pc);
+    }
+
+    fn clear(&mut self) {
+        self.pcs.clear();
+    }
+
+    fn slots(&self, pc: usize) -> &[Option<usize>] {
+        &self.slots[pc * self.slots_count..(pc + 1) * self.slots_count]
+    }
+
+    fn slots_mut(&mut self, pc: usize) -> &mut [Option<usize>] {
+        &mut self.slots[pc * self.slots_count..(pc + 1) * self.slots_count]
+    }
+}
+
+enum Frame {
+    Explore(usize),
+    // Puts back the slot value from before a save, once the instructions after it are explored
+    RestoreSlot(usize, Option<usize>),
+}
+
+// Follows the instructions that don't consume a char from a position, with an explicit stack so
+// that long programs can't overflow the call stack
+struct Closure<'c, 'p, 'a> {
+    program: &'p Program,
+    input: Input<'a>,
+    at: usize,
+    slots: &'c mut [Option<usize>],
+    stack: &'c mut Vec<Frame>,
+}
+
+impl Closure<'_, '_, '_> {
+    // Adds the threads reachable from the instruction, in order of priority, each instruction
+    // being taken only by the first thread reaching it
+    fn add_thread(&mut self, threads: &mut Threads, pc: usize) {
+        self.stack.push(Frame::Explore(pc));
+        while let Some(frame) = self.stack.pop() {
+            let pc = match frame {
+                Frame::Explore(pc) => pc,
+                Frame::RestoreSlot(slot, value) => {
+                    self.slots[slot] = value;
+                    continue;
+                }
+            };
+            if threads.contains(pc) {
+                continue;
+            }
+            threads.insert(pc);
+            match &self.program.instructions[pc] {
+                Instruction::Split(first, second) => {
+                    self.stack.push(Frame::Explore(*second));
+                    self.stack.push(Frame::Explore(*first));
+                }
+                Instruction::Jump(to) => self.stack.push(Frame::Explore(*to)),
+                Instruction::Save(slot) => {
+                    if let Some(slot_value) = self.slots.get_mut(*slot) {
+                        self.stack.push(Frame::RestoreSlot(*slot, *slot_value));
+                        *slot_value = Some(self.at);
+                    }
+                    self.stack.push(Frame::Explore(pc + 1));
+                }
+                Instruction::StartOfString => {
+                    if self.at == 0 {
+                        self.stack.push(Frame::Explore(pc + 1));
+                    }
+                }
+                Instruction::EndOfString => {
+                    if self.at == self.input.len() {
+                        self.stack.push(Frame::Explore(pc + 1));
+                    }
+                }
+                Instruction::EndOfLastLine => {
+                    if self.input.is_end_of_last_line(self.at) {
+                        self.stack.push(Frame::Explore(pc + 1));
+                    }
+                }
+                Instruction::StartOfLine => {
+                    if self.input.is_start_of_line(self.at) {
+                        self.stack.push(Frame::Explore(pc + 1));
+                    }
+                }
+                Instruction::EndOfLine => {
+                    if self.input.is_end_of_line(self.at) {
+                        self.stack.push(Frame::Explore(pc + 1));
+                    }
+                }
+                Instruction::WordBoundary => {
+                    if self.input.is_word_boundary(self.at) {
+                        self.stack.push(Frame::Explore(pc + 1));
+                    }
+                }
+                Instruction::NonWordBoundary => {
+                    if !self.input.is_word_boundary(self.at) {
+                        self.stack.push(Frame::Explore(pc + 1));
+                    }
+                }
+                Instruction::Char(_) | Instruction::Match => {
+                    threads.slots_mut(pc).copy_from_slice(self.slots);
+                }
+                // The programs with these are only run by the backtracker
+                Instruction::Backreference { .. }
+                | Instruction::Lookahead { .. }
+                | Instruction::Lookbehind { .. } => {}
+            }
+        }
+    }
+}
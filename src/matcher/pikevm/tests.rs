@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tests {
+    use crate::matcher::backtrack;
+    use crate::matcher::literal::Literals;
+    use crate::matcher::pattern::parse_pattern;
+    use crate::matcher::pikevm::{captures_at, Cache};
+    use crate::matcher::program::compile;
+    use crate::matcher::{Input, Mode};
+
+    #[test]
+    fn test_captures_at_like_backtracker() {
+        let cases = [
+            ("a+ab", "aaab"),
+            ("\\w+s", "dogs and cats"),
+            ("(a|ab)(c|bcd)(d*)", "abcd"),
+            ("(a*)+b", "aab"),
+            ("(a*)*", "b"),
+            ("\"(.+?)\"", "say \"hi\" and \"bye\""),
+            ("(\\d{2,3}?)(\\d*)", "12345"),
+            ("^(ca|cat)s?$", "cats"),
+            ("x(y?)+z", "xyyz"),
+            ("(a|b)*c", "abbac"),
+            ("ü+(ß)", "aüüß"),
+            ("\\b\\w+\\b", "a cat-like dog"),
+            ("\\Bat\\b", "bat at"),
+            ("a\\b", "ab a"),
+            ("(\\b|x)+y", "xy y"),
+            ("$", "end"),
+            ("((a|b)c)+", "acbcx"),
+            ("(a)|(b)(c)|", "xbc"),
+            ("(?m)(^\\w|\\w$)", "ab\ncd"),
+            ("(?i)(A|b)+", "xaBAy"),
+            ("(^a|b$)", "ab"),
+            ("(x|^)(y|$)", "xy"),
+            ("(a\\Z|a\n)", "a\n"),
+            ("a\\Z(\n?)", "a\n"),
+            // A repetition matching no chars is not followed by another one
+            ("b*\\s(c??)+", " c"),
+            ("(c??)+d", "cd"),
+            ("(a??){2}b", "ab"),
+            ("(a|\\b)*?c", "ac"),
+        ];
+        for (pattern_string, input_string) in cases {
+            let patterns = parse_pattern(pattern_string).unwrap();
+            let program = compile(&patterns).unwrap();
+            let literals = Literals::new(&patterns, Mode::Unicode);
+            for position in 0..=input_string.len() {
+                if !input_string.is_char_boundary(position) {
+                    continue;
+                }
+                let input = Input::new(input_string.as_bytes(), Mode::Unicode);
+                assert_eq!(
+                    captures_at(
+                        &program,
+                        program.slots_count,
+                        &mut Cache::default(),
+                        &literals,
+                        input,
+                        position
+                    ),
+                    backtrack::captures_at(&program, &literals, input, position),
+                    "{} on {:?} from {}",
+                    pattern_string,
+                    input_string,
+                    position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_captures_at_bytes_like_backtracker() {
+        let cases: [(&str, &[u8], Mode); 5] = [
+            ("a.c", b"a\xffc", Mode::Unicode),
+            ("a[^b]c", b"a\xffc", Mode::Unicode),
+            ("a.+", b"xa\xe2\x82\xac\xe2\x82", Mode::Unicode),
+            ("a.c", b"a\xffc", Mode::Bytes),
+            ("\u{e9}+", b"\xe9\xe9", Mode::Bytes),
+        ];
+        for (pattern_string, input_bytes, mode) in cases {
+            let patterns = parse_pattern(pattern_string).unwrap();
+            let program = compile(&patterns).unwrap();
+            let literals = Literals::new(&patterns, mode);
+            for position in 0..=input_bytes.len() {
+                let input = Input::new(input_bytes, mode);
+                assert_eq!(
+                    captures_at(
+                        &program,
+                        program.slots_count,
+                        &mut Cache::default(),
+                        &literals,
+                        input,
+                        position
+                    ),
+                    backtrack::captures_at(&program, &literals, input, position),
+                    "{} on {:?} from {}",
+                    pattern_string,
+                    input_bytes,
+                    position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_captures_at_in_linear_time() {
+        // The backtracker tries exponentially many ways to split the "a"s between the groups
+        let patterns = parse_pattern("(a*)*(a*)*b").unwrap();
+        let program = compile(&patterns).unwrap();
+        let literals = Literals::new(&patterns, Mode::Unicode);
+        let input_string = "a".repeat(10_000);
+        let input = Input::new(input_string.as_bytes(), Mode::Unicode);
+        assert_eq!(
+            captures_at(
+                &program,
+                program.slots_count,
+                &mut Cache::default(),
+                &literals,
+                input,
+                0
+            ),
+            None
+        );
+
+        let input_string = input_string + "b";
+        let input = Input::new(input_string.as_bytes(), Mode::Unicode);
+        let captures = captures_at(
+            &program,
+            program.slots_count,
+            &mut Cache::default(),
+            &literals,
+            input,
+            0,
+        )
+        .unwrap();
+        assert_eq!(captures.as_str(0), Some(input_string.as_str()));
+    }
+
+    #[test]
+    fn test_captures_at_with_cache() {
+        // The threads left by an input or a smaller program don't leak into the next match
+        let mut cache = Cache::default();
+        for (pattern_string, input_string, expected) in [
+            ("a(b)", "xab", Some("ab")),
+            ("a(b)", "aab", Some("ab")),
+            ("a(b)", "ac", None),
+            ("(x)(y)(z)|yz", "xyz yz", Some("xyz")),
+            ("a(b)", "ab", Some("ab")),
+        ] {
+            let patterns = parse_pattern(pattern_string).unwrap();
+            let program = compile(&patterns).unwrap();
+            let literals = Literals::new(&patterns, Mode::Unicode);
+            let input = Input::new(input_string.as_bytes(), Mode::Unicode);
+            assert_eq!(
+                captures_at(
+                    &program,
+                    program.slots_count,
+                    &mut cache,
+                    &literals,
+                    input,
+                    0
+                )
+                .and_then(|captures| captures.as_str(0)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_captures_at_whole_match() {
+        // Without the slots of the groups, only the whole match is recorded
+        let patterns = parse_pattern("(a)(b|c)+").unwrap();
+        let program = compile(&patterns).unwrap();
+        let literals = Literals::new(&patterns, Mode::Unicode);
+        let input = Input::new(b"xabcb", Mode::Unicode);
+        let captures =
+            captures_at(&program, 2, &mut Cache::default(), &literals, input, 0).unwrap();
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures.as_str(0), Some("abcb"));
+        assert_eq!(captures.get(1), None);
+    }
+}
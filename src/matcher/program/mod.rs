@@ -0,0 +1,295 @@
+use super::pattern::{ClassItem, Pattern};
+use super::{groups_count, is_anchored, length_range};
+use std::mem::size_of;
+
+#[allow(clippy::module_inception)]
+mod tests;
+
+// Counted repetitions are compiled into copies of the repeated pattern, so nesting them can make
+// the program grow exponentially, in which case the pattern is too large to be matched in linear
+// time. Like in the regex crate, the program can take up to about 10 MB
+const MAX_PROGRAM_SIZE: usize = 10 << 20;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Instruction {
+    // Consumes a char matching a pattern that matches a single char
+    Char(Pattern),
+    // Continues from both instructions, preferring the first one
+    Split(usize, usize),
+    Jump(usize),
+    // Records the position in a slot, the slots 2n and 2n + 1 being the start and end of group n
+    Save(usize),
+    StartOfString,
+    EndOfString,
+    EndOfLastLine,
+    StartOfLine,
+    EndOfLine,
+    WordBoundary,
+    NonWordBoundary,
+    // Consumes the text that the group captured last, failing if it didn't capture any
+    Backreference {
+        number: usize,
+        is_case_insensitive: bool,
+    },
+    // Continues from `end` if the instructions after it match from the position, or don't if it's
+    // negated
+    Lookahead {
+        is_negated: bool,
+        end: usize,
+    },
+    // Continues from `end` if the instructions after it match up to the position from between
+    // `min_length` and `max_length` chars before it, or don't if it's negated
+    Lookbehind {
+        is_negated: bool,
+        min_length: usize,
+        max_length: usize,
+        end: usize,
+    },
+    Match,
+}
+
+// A Thompson NFA, whose threads start at the first instruction
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub slots_count: usize,
+    pub is_anchored: bool,
+    // Whether it has backreferences or lookarounds, that only the backtracker runs
+    pub is_backtracked: bool,
+}
+
+// Compiles the patterns, unless the program would be too large
+pub fn compile(patterns: &[Pattern]) -> Option<Program> {
+    let groups_count: usize = patterns.iter().map(groups_count).sum();
+    let mut compiler = Compiler {
+        instructions: Vec::new(),
+        size: 0,
+        is_backtracked: false,
+    };
+    compiler.push(Instruction::Save(0))?;
+    compiler.compile_patterns(patterns, 1)?;
+    compiler.push(Instruction::Save(1))?;
+    compiler.push(Instruction::Match)?;
+    Some(Program {
+        instructions: compiler.instructions,
+        slots_count: 2 * (groups_count + 1),
+        is_anchored: is_anchored(patterns),
+        is_backtracked: compiler.is_backtracked,
+    })
+}
+
+struct Compiler {
+    instructions: Vec<Instruction>,
+    // The memory taken by the instructions so far
+    size: usize,
+    is_backtracked: bool,
+}
+
+impl Compiler {
+    // Pushes the instruction, returning its index
+    fn push(&mut self, instruction: Instruction) -> Option<usize> {
+        self.size += instruction_size(&instruction);
+        if self.size > MAX_PROGRAM_SIZE {
+            return None;
+        }
+        self.instructions.push(instruction);
+        Some(self.instructions.len() - 1)
+    }
+
+    // Points a placeholder jump or split pushed before its targets were known
+    fn patch(&mut self, index: usize, instruction: Instruction) {
+        self.instructions[index] = instruction;
+    }
+
+    fn next_index(&self) -> usize {
+        self.instructions.len()
+    }
+
+    // Compiles the patterns in sequence, numbering the capturing groups from `group_index`
+    fn compile_patterns(&mut self, patterns: &[Pattern], group_index: usize) -> Option<()> {
+        let mut group_index = group_index;
+        for pattern in patterns {
+            self.compile_pattern(pattern, group_index)?;
+            group_index += groups_count(pattern);
+        }
+        Some(())
+    }
+
+    // Fails only if the program would be too large
+    fn compile_pattern(&mut self, pattern: &Pattern, group_index: usize) -> Option<()> {
+        match pattern {
+            Pattern::Literal(_)
+            | Pattern::Digit
+            | Pattern::Alphanumeric
+            | Pattern::Whitespace
+            | Pattern::NonDigit
+            | Pattern::NonAlphanumeric
+            | Pattern::NonWhitespace
+            | Pattern::CharacterClass(_)
+            | Pattern::UnicodeProperty { .. }
+            | Pattern::Wildcard
+            | Pattern::AnyChar => self.push(Instruction::Char(pattern.clone())).map(|_| ()),
+            Pattern::StartOfString => self.push(Instruction::StartOfString).map(|_| ()),
+            Pattern::EndOfString => self.push(Instruction::EndOfString).map(|_| ()),
+            Pattern::EndOfLastLine => self.push(Instruction::EndOfLastLine).map(|_| ()),
+            Pattern::StartOfLine => self.push(Instruction::StartOfLine).map(|_| ()),
+            Pattern::EndOfLine => self.push(Instruction::EndOfLine).map(|_| ()),
+            Pattern::WordBoundary => self.push(Instruction::WordBoundary).map(|_| ()),
+            Pattern::NonWordBoundary => self.push(Instruction::NonWordBoundary).map(|_| ()),
+            Pattern::Repeat {
+                pattern,
+                min,
+                max,
+                is_lazy,
+            } => self.compile_repeat(pattern, *min, *max, *is_lazy, group_index),
+            Pattern::CapturingGroup(group) | Pattern::NamedCapturingGroup(_, group) => {
+                self.push(Instruction::Save(2 * group_index))?;
+                self.compile_patterns(group, group_index + 1)?;
+                self.push(Instruction::Save(2 * group_index + 1))
+                    .map(|_| ())
+            }
+            Pattern::NonCapturingGroup(group) => self.compile_patterns(group, group_index),
+            Pattern::Alternation(alternatives) => {
+                self.compile_alternation(alternatives, group_index)
+            }
+            Pattern::Backreference(number) => self.compile_backreference(*number, false),
+            Pattern::CaseInsensitive(case_insensitive_pattern) => {
+                match **case_insensitive_pattern {
+                    Pattern::Backreference(number) => self.compile_backreference(number, true),
+                    // The other ones match a single char
+                    _ => self.push(Instruction::Char(pattern.clone())).map(|_| ()),
+                }
+            }
+            Pattern::Lookahead {
+                patterns,
+                is_negated,
+            } => self.compile_lookaround(patterns, group_index, |end| Instruction::Lookahead {
+                is_negated: *is_negated,
+                end,
+            }),
+            Pattern::Lookbehind {
+                patterns,
+                is_negated,
+            } => {
+                // The parser only accepts the lookbehinds with a longest match
+                let (min_length, max_length) = length_range(patterns);
+                self.compile_lookaround(patterns, group_index, |end| Instruction::Lookbehind {
+                    is_negated: *is_negated,
+                    min_length,
+                    max_length: max_length.unwrap_or(usize::MAX),
+                    end,
+                })
+            }
+        }
+    }
+
+    fn compile_backreference(&mut self, number: usize, is_case_insensitive: bool) -> Option<()> {
+        self.is_backtracked = true;
+        self.push(Instruction::Backreference {
+            number,
+            is_case_insensitive,
+        })
+        .map(|_| ())
+    }
+
+    // The lookaround patterns follow it, up to a match of their own
+    fn compile_lookaround(
+        &mut self,
+        patterns: &[Pattern],
+        group_index: usize,
+        lookaround: impl FnOnce(usize) -> Instruction,
+    ) -> Option<()> {
+        self.is_backtracked = true;
+        let lookaround_index = self.push(Instruction::Jump(0))?;
+        self.compile_patterns(patterns, group_index)?;
+        self.push(Instruction::Match)?;
+        let end = self.next_index();
+        self.patch(lookaround_index, lookaround(end));
+        Some(())
+    }
+
+    // Each alternative but the last one is preceded by a split preferring it over the next ones,
+    // and followed by a jump past the last one
+    fn compile_alternation(&mut self, groups: &[Vec<Pattern>], group_index: usize) -> Option<()> {
+        let mut group_index = group_index;
+        let mut jumps = Vec::new();
+        for (index, group) in groups.iter().enumerate() {
+            let is_last = index == groups.len() - 1;
+            let split = if is_last {
+                None
+            } else {
+                Some(self.push(Instruction::Jump(0))?)
+            };
+            self.compile_patterns(group, group_index)?;
+            group_index += group.iter().map(groups_count).sum::<usize>();
+            if let Some(split) = split {
+                jumps.push(self.push(Instruction::Jump(0))?);
+                self.patch(split, Instruction::Split(split + 1, self.next_index()));
+            }
+        }
+        let end = self.next_index();
+        for jump in jumps {
+            self.patch(jump, Instruction::Jump(end));
+        }
+        Some(())
+    }
+
+    // The minimum repetitions are copies of the pattern, followed by a loop when there is no
+    // maximum, or by nested optional copies up to the maximum
+    fn compile_repeat(
+        &mut self,
+        pattern: &Pattern,
+        min: usize,
+        max: Option<usize>,
+        is_lazy: bool,
+        group_index: usize,
+    ) -> Option<()> {
+        for _ in 0..min {
+            self.compile_pattern(pattern, group_index)?;
+        }
+        let split = |repeat: usize, skip: usize| {
+            if is_lazy {
+                Instruction::Split(skip, repeat)
+            } else {
+                Instruction::Split(repeat, skip)
+            }
+        };
+
+        let Some(max) = max else {
+            let loop_start = self.push(Instruction::Jump(0))?;
+            self.compile_pattern(pattern, group_index)?;
+            self.push(Instruction::Jump(loop_start))?;
+            let end = self.next_index();
+            self.patch(loop_start, split(loop_start + 1, end));
+            return Some(());
+        };
+
+        let mut splits = Vec::new();
+        for _ in min..max {
+            splits.push(self.push(Instruction::Jump(0))?);
+            self.compile_pattern(pattern, group_index)?;
+        }
+        let end = self.next_index();
+        for split_index in splits {
+            self.patch(split_index, split(split_index + 1, end));
+        }
+        Some(())
+    }
+}
+
+// The memory taken by the instruction, with the items of the character class it matches
+fn instruction_size(instruction: &Instruction) -> usize {
+    let heap_size = match instruction {
+        Instruction::Char(Pattern::CharacterClass(class)) => {
+            class.items.len() * size_of::<ClassItem>()
+        }
+        Instruction::Char(Pattern::CaseInsensitive(pattern)) => match &**pattern {
+            Pattern::CharacterClass(class) => {
+                size_of::<Pattern>() + class.items.len() * size_of::<ClassItem>()
+            }
+            _ => size_of::<Pattern>(),
+        },
+        _ => 0,
+    };
+    size_of::<Instruction>() + heap_size
+}
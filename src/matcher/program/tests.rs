@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use crate::matcher::pattern::{parse_pattern, Pattern};
+    use crate::matcher::program::{compile, Instruction, Program};
+
+    fn compile_pattern(pattern_string: &str) -> Option<Program> {
+        compile(&parse_pattern(pattern_string).unwrap())
+    }
+
+    #[test]
+    fn test_compile_literals() {
+        assert_eq!(
+            compile_pattern("^ab$"),
+            Some(Program {
+                instructions: vec![
+                    Instruction::Save(0),
+                    Instruction::StartOfString,
+                    Instruction::Char(Pattern::Literal('a')),
+                    Instruction::Char(Pattern::Literal('b')),
+                    Instruction::EndOfString,
+                    Instruction::Save(1),
+                    Instruction::Match,
+                ],
+                slots_count: 2,
+                is_anchored: true,
+                is_backtracked: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_compile_repeat() {
+        assert_eq!(
+            compile_pattern("a+b*?").map(|program| program.instructions),
+            Some(vec![
+                Instruction::Save(0),
+                Instruction::Char(Pattern::Literal('a')),
+                Instruction::Split(3, 5),
+                Instruction::Char(Pattern::Literal('a')),
+                Instruction::Jump(2),
+                Instruction::Split(8, 6),
+                Instruction::Char(Pattern::Literal('b')),
+                Instruction::Jump(5),
+                Instruction::Save(1),
+                Instruction::Match,
+            ])
+        );
+        assert_eq!(
+            compile_pattern("a{1,3}").map(|program| program.instructions),
+            Some(vec![
+                Instruction::Save(0),
+                Instruction::Char(Pattern::Literal('a')),
+                Instruction::Split(3, 6),
+                Instruction::Char(Pattern::Literal('a')),
+                Instruction::Split(5, 6),
+                Instruction::Char(Pattern::Literal('a')),
+                Instruction::Save(1),
+                Instruction::Match,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_compile_groups() {
+        assert_eq!(
+            compile_pattern("(a|b)(c)"),
+            Some(Program {
+                instructions: vec![
+                    Instruction::Save(0),
+                    Instruction::Save(2),
+                    Instruction::Split(3, 5),
+                    Instruction::Char(Pattern::Literal('a')),
+                    Instruction::Jump(6),
+                    Instruction::Char(Pattern::Literal('b')),
+                    Instruction::Save(3),
+                    Instruction::Save(4),
+                    Instruction::Char(Pattern::Literal('c')),
+                    Instruction::Save(5),
+                    Instruction::Save(1),
+                    Instruction::Match,
+                ],
+                slots_count: 6,
+                is_anchored: false,
+                is_backtracked: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_compile_for_backtracker() {
+        assert_eq!(
+            compile_pattern("(a)\\1"),
+            Some(Program {
+                instructions: vec![
+                    Instruction::Save(0),
+                    Instruction::Save(2),
+                    Instruction::Char(Pattern::Literal('a')),
+                    Instruction::Save(3),
+                    Instruction::Backreference {
+                        number: 1,
+                        is_case_insensitive: false,
+                    },
+                    Instruction::Save(1),
+                    Instruction::Match,
+                ],
+                slots_count: 4,
+                is_anchored: false,
+                is_backtracked: true,
+            })
+        );
+        assert_eq!(
+            compile_pattern("(?<!a)b").map(|program| program.instructions),
+            Some(vec![
+                Instruction::Save(0),
+                Instruction::Lookbehind {
+                    is_negated: true,
+                    min_length: 1,
+                    max_length: 1,
+                    end: 4,
+                },
+                Instruction::Char(Pattern::Literal('a')),
+                Instruction::Match,
+                Instruction::Char(Pattern::Literal('b')),
+                Instruction::Save(1),
+                Instruction::Match,
+            ])
+        );
+        assert!(compile_pattern("a(?=b)").is_some_and(|program| program.is_backtracked));
+        assert!(compile_pattern("a|b").is_some_and(|program| !program.is_backtracked));
+    }
+
+    #[test]
+    fn test_compile_too_large() {
+        assert_eq!(compile_pattern("(a{1000}){1000}"), None);
+        assert_eq!(compile_pattern("[a-z0-9_]{100000}"), None);
+        assert!(compile_pattern("(a{100}){100}").is_some());
+        assert!(compile_pattern("x.{0,5000}y").is_some());
+    }
+}
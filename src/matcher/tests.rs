@@ -1,190 +1,854 @@
 #[cfg(test)]
 mod tests {
 
-    use crate::matcher::match_pattern;
+    use crate::matcher::{
+        captures, find, find_iter, match_pattern, Flags, Match, Mode, PatternError, Regex,
+    };
 
     #[test]
     fn test_match_pattern_single_letter() {
-        assert_eq!(match_pattern("hello world", "h"), true);
-        assert_eq!(match_pattern("hello world", "w"), false);
-        assert_eq!(match_pattern("hello world", "hello"), true);
-        assert_eq!(match_pattern("hello world", "ello"), false);
-        assert_eq!(match_pattern("hello world", "hel"), true);
-        assert_eq!(match_pattern("hello world", "hwd"), false);
-        assert_eq!(match_pattern("hello world", "hez"), false);
-        assert_eq!(match_pattern("123e1z2h3", "hez"), false);
-        assert_eq!(match_pattern("123h1e2z3", "123"), true);
+        assert_eq!(match_pattern("hello world", "h"), Ok(true));
+        assert_eq!(match_pattern("hello world", "w"), Ok(true));
+        assert_eq!(match_pattern("hello world", "hello"), Ok(true));
+        assert_eq!(match_pattern("hello world", "ello"), Ok(true));
+        assert_eq!(match_pattern("hello world", "hel"), Ok(true));
+        assert_eq!(match_pattern("hello world", "hwd"), Ok(false));
+        assert_eq!(match_pattern("hello world", "hez"), Ok(false));
+        assert_eq!(match_pattern("123e1z2h3", "hez"), Ok(false));
+        assert_eq!(match_pattern("123h1e2z3", "123"), Ok(true));
     }
 
     #[test]
     fn test_match_pattern_single_digits() {
-        assert_eq!(match_pattern("world", r"\d"), false);
-        assert_eq!(match_pattern("1 world", r"\d"), true);
-        assert_eq!(match_pattern("2world", r"\d"), true);
-        assert_eq!(match_pattern("h3llo", r"\d"), false);
-        assert_eq!(match_pattern("h3llo", r"h\d"), true);
-        assert_eq!(match_pattern("Cia0", r"\d"), false);
-        assert_eq!(match_pattern("1 orange", r"\d orange"), true);
-        assert_eq!(match_pattern("1 orange", r"\d apple"), false);
-        assert_eq!(match_pattern("orange 2", r"orange \d"), true);
-        assert_eq!(match_pattern("orange 2", r"apple \d"), false);
-        assert_eq!(match_pattern("sally has 3 apples", r"\d apple"), true);
-        assert_eq!(match_pattern("sally has two apples", r"\d apple"), false);
+        assert_eq!(match_pattern("world", r"\d"), Ok(false));
+        assert_eq!(match_pattern("1 world", r"\d"), Ok(true));
+        assert_eq!(match_pattern("2world", r"\d"), Ok(true));
+        assert_eq!(match_pattern("h3llo", r"\d"), Ok(true));
+        assert_eq!(match_pattern("h3llo", r"h\d"), Ok(true));
+        assert_eq!(match_pattern("Cia0", r"\d"), Ok(true));
+        assert_eq!(match_pattern("1 orange", r"\d orange"), Ok(true));
+        assert_eq!(match_pattern("1 orange", r"\d apple"), Ok(false));
+        assert_eq!(match_pattern("orange 2", r"orange \d"), Ok(true));
+        assert_eq!(match_pattern("orange 2", r"apple \d"), Ok(false));
+        assert_eq!(match_pattern("sally has 3 apples", r"\d apple"), Ok(true));
+        assert_eq!(
+            match_pattern("sally has two apples", r"\d apple"),
+            Ok(false)
+        );
     }
 
     #[test]
     fn test_match_pattern_alphanumeric() {
-        assert_eq!(match_pattern("hello", r"\w"), true);
-        assert_eq!(match_pattern("2123", r"\w"), true);
-        assert_eq!(match_pattern("___", r"\w"), true);
-        assert_eq!(match_pattern("_he110_", r"\w"), true);
-        assert_eq!(match_pattern("_he110_", r"\wZ"), false);
-        assert_eq!(match_pattern("£$%a", r"\w"), true);
-        assert_eq!(match_pattern("$A", r"\w"), true);
-        assert_eq!(match_pattern("xA", r"x\w"), true);
-        assert_eq!(match_pattern("---", r"\w"), false);
-        assert_eq!(match_pattern("é", r"\w"), true);
-        assert_eq!(match_pattern("ç", r"\w"), true);
-        assert_eq!(match_pattern("#A#", r"\w\w#"), false);
-        assert_eq!(match_pattern("#A#", r"#\w#"), true);
-        assert_eq!(match_pattern("3 dogs", r"\d \w\w\ws"), true);
-        assert_eq!(match_pattern("has 4 dogs", r"has \d \w\w\ws"), true);
-        assert_eq!(match_pattern("has 1 dog", r"has \d \w\w\ws"), false);
-        assert_eq!(match_pattern("a 1 dog", r"a \d \w\w\ws"), false);
-        assert_eq!(match_pattern("a 1 dog", r"a \d \w\w\w"), true);
+        assert_eq!(match_pattern("hello", r"\w"), Ok(true));
+        assert_eq!(match_pattern("2123", r"\w"), Ok(true));
+        assert_eq!(match_pattern("___", r"\w"), Ok(true));
+        assert_eq!(match_pattern("_he110_", r"\w"), Ok(true));
+        assert_eq!(match_pattern("_he110_", r"\wZ"), Ok(false));
+        assert_eq!(match_pattern("£$%a", r"\w"), Ok(true));
+        assert_eq!(match_pattern("$A", r"\w"), Ok(true));
+        assert_eq!(match_pattern("xA", r"x\w"), Ok(true));
+        assert_eq!(match_pattern("---", r"\w"), Ok(false));
+        assert_eq!(match_pattern("é", r"\w"), Ok(true));
+        assert_eq!(match_pattern("ç", r"\w"), Ok(true));
+        assert_eq!(match_pattern("#A#", r"\w\w#"), Ok(false));
+        assert_eq!(match_pattern("#A#", r"#\w#"), Ok(true));
+        assert_eq!(match_pattern("3 dogs", r"\d \w\w\ws"), Ok(true));
+        assert_eq!(match_pattern("has 4 dogs", r"has \d \w\w\ws"), Ok(true));
+        assert_eq!(match_pattern("has 1 dog", r"has \d \w\w\ws"), Ok(false));
+        assert_eq!(match_pattern("a 1 dog", r"a \d \w\w\ws"), Ok(false));
+        assert_eq!(match_pattern("a 1 dog", r"a \d \w\w\w"), Ok(true));
+    }
+
+    #[test]
+    fn test_match_pattern_shorthand_classes() {
+        assert_eq!(match_pattern("cats", r"cat\s"), Ok(false));
+        assert_eq!(match_pattern("cat\tdog", r"cat\sdog"), Ok(true));
+        assert_eq!(match_pattern("a\u{2003}b", r"a\sb"), Ok(true));
+        assert_eq!(match_pattern("   ", r"\S"), Ok(false));
+        assert_eq!(match_pattern(" x ", r"\S"), Ok(true));
+        assert_eq!(match_pattern("123", r"\D"), Ok(false));
+        assert_eq!(match_pattern("12a3", r"\d\D\d"), Ok(true));
+        assert_eq!(match_pattern("snake_case", r"\W"), Ok(false));
+        assert_eq!(match_pattern("kebab-case", r"b\Wc"), Ok(true));
+        assert_eq!(match_pattern("a1 b", r"^[\S\D]+$"), Ok(true));
+    }
+
+    #[test]
+    fn test_match_pattern_word_boundary() {
+        assert_eq!(match_pattern("a cat sat", r"\bcat\b"), Ok(true));
+        assert_eq!(match_pattern("concatenate", r"\bcat\b"), Ok(false));
+        assert_eq!(match_pattern("cat", r"^\bcat\b$"), Ok(true));
+        assert_eq!(match_pattern("concatenate", r"\Bcat\B"), Ok(true));
+        assert_eq!(match_pattern("cat", r"\Bcat"), Ok(false));
+        assert_eq!(match_pattern("café", r"caf\b"), Ok(false));
+        assert_eq!(match_pattern("a-", r"-\b"), Ok(false));
+        assert_eq!(match_pattern("", r"\B"), Ok(true));
+        assert_eq!(match_pattern("", r"\b"), Ok(false));
+        // The backtracker matches them too
+        assert_eq!(match_pattern("it is it", r"\b(\w+)\b.*\b\1\b"), Ok(true));
     }
 
     #[test]
     fn test_match_pattern_positive_group() {
-        assert_eq!(match_pattern("c", "[abc]"), true);
-        assert_eq!(match_pattern("d", "[abc]"), false);
-        assert_eq!(match_pattern("ab", "[abc][abc]"), true);
-        assert_eq!(match_pattern("ad", "[abc][abc]"), false);
-        assert_eq!(match_pattern("hello", "[ytz]"), false);
-        assert_eq!(match_pattern("hello", "[abctyjh]"), true);
-        assert_eq!(match_pattern("eh", "[abctyjh]"), true);
+        assert_eq!(match_pattern("c", "[abc]"), Ok(true));
+        assert_eq!(match_pattern("d", "[abc]"), Ok(false));
+        assert_eq!(match_pattern("ab", "[abc][abc]"), Ok(true));
+        assert_eq!(match_pattern("ad", "[abc][abc]"), Ok(false));
+        assert_eq!(match_pattern("hello", "[ytz]"), Ok(false));
+        assert_eq!(match_pattern("hello", "[abctyjh]"), Ok(true));
+        assert_eq!(match_pattern("eh", "[abctyjh]"), Ok(true));
     }
 
     #[test]
     fn test_match_pattern_negative_group() {
-        assert_eq!(match_pattern("c", "[^abc]"), false);
-        assert_eq!(match_pattern("d", "[^abc]"), true);
-        assert_eq!(match_pattern("ab", "[^abc][^abc]"), false);
-        assert_eq!(match_pattern("ad", "[^abc][^abc]"), false);
-        assert_eq!(match_pattern("xy", "[^abc][^abc]"), true);
-        assert_eq!(match_pattern("hello", "[^ytz]"), true);
-        assert_eq!(match_pattern("he", "[^abctyjh]"), true);
-        assert_eq!(match_pattern("hh", "[^abctyjh]"), false);
+        assert_eq!(match_pattern("c", "[^abc]"), Ok(false));
+        assert_eq!(match_pattern("d", "[^abc]"), Ok(true));
+        assert_eq!(match_pattern("ab", "[^abc][^abc]"), Ok(false));
+        assert_eq!(match_pattern("ad", "[^abc][^abc]"), Ok(false));
+        assert_eq!(match_pattern("xy", "[^abc][^abc]"), Ok(true));
+        assert_eq!(match_pattern("hello", "[^ytz]"), Ok(true));
+        assert_eq!(match_pattern("he", "[^abctyjh]"), Ok(true));
+        assert_eq!(match_pattern("hh", "[^abctyjh]"), Ok(false));
+    }
+
+    #[test]
+    fn test_match_pattern_character_class() {
+        assert_eq!(match_pattern("q", "[a-z0-9]"), Ok(true));
+        assert_eq!(match_pattern("7", "[a-z0-9]"), Ok(true));
+        assert_eq!(match_pattern("-", "[a-z0-9]"), Ok(false));
+        assert_eq!(match_pattern("Z", "[a-z0-9]"), Ok(false));
+        assert_eq!(match_pattern("a-a", "^[a-]+$"), Ok(true));
+        assert_eq!(match_pattern("x]", r"x[\]]"), Ok(true));
+        assert_eq!(match_pattern("x]", "x[]]"), Ok(true));
+        assert_eq!(match_pattern("tab\there", r"b[\s]h"), Ok(true));
+        assert_eq!(match_pattern("id: 42", r"[\d:]{3}"), Ok(false));
+        assert_eq!(match_pattern("at 12:30", r"[\d:]{3}"), Ok(true));
+        assert_eq!(match_pattern("snake_case", r"^[\w]+$"), Ok(true));
+        assert_eq!(match_pattern("naïve", "^[a-zà-ÿ]+$"), Ok(true));
+        assert_eq!(match_pattern("abc", "[^a-c]"), Ok(false));
+        assert_eq!(match_pattern("abc ", r"[^\w]"), Ok(true));
+        assert_eq!(
+            match_pattern("a", "[z-a]"),
+            Err(PatternError::InvalidClassRange { column: 2 })
+        );
+    }
+
+    #[test]
+    fn test_match_pattern_posix_class() {
+        assert_eq!(match_pattern("naïve", "^[[:alpha:]]+$"), Ok(true));
+        assert_eq!(match_pattern("a1", "^[[:alpha:]]+$"), Ok(false));
+        assert_eq!(match_pattern("a1", "^[[:alnum:]]+$"), Ok(true));
+        assert_eq!(match_pattern("a\u{a0}\tb", "a[[:blank:]]{2}b"), Ok(true));
+        assert_eq!(match_pattern("a\nb", "a[[:blank:]]b"), Ok(false));
+        assert_eq!(match_pattern("a\x0bb", "a[[:space:]]b"), Ok(true));
+        assert_eq!(match_pattern("a\x7fb", "a[[:cntrl:]]b"), Ok(true));
+        assert_eq!(match_pattern("٣", "[[:digit:]]"), Ok(false));
+        assert_eq!(match_pattern("0xBEEF", "^0x[[:xdigit:]]+$"), Ok(true));
+        assert_eq!(match_pattern("Élan", "^[[:upper:]][[:lower:]]+$"), Ok(true));
+        assert_eq!(match_pattern("«a»", "^[[:punct:]]a[[:punct:]]$"), Ok(true));
+        assert_eq!(match_pattern("a b", "^[[:graph:]]+$"), Ok(false));
+        assert_eq!(match_pattern("a b", "^[[:print:]]+$"), Ok(true));
+        assert_eq!(match_pattern("ab", "[^[:alpha:]]"), Ok(false));
+        assert_eq!(
+            match_pattern("a", "[[:letter:]]"),
+            Err(PatternError::UnknownPosixClass { column: 2 })
+        );
+    }
+
+    #[test]
+    fn test_match_pattern_unicode_property() {
+        assert_eq!(match_pattern("naïve", r"^\p{L}+$"), Ok(true));
+        assert_eq!(match_pattern("a1", r"^\pL+$"), Ok(false));
+        assert_eq!(match_pattern("λόγος", r"^\p{Greek}+$"), Ok(true));
+        assert_eq!(match_pattern("logos", r"\p{Greek}"), Ok(false));
+        assert_eq!(match_pattern("٣", r"\p{Nd}"), Ok(true));
+        assert_eq!(match_pattern("漢字", r"^\p{Han}+$"), Ok(true));
+        assert_eq!(match_pattern("漢字かな", r"^\P{Han}"), Ok(false));
+        assert_eq!(match_pattern("漢字かな", r"\P{Han}+$"), Ok(true));
+        assert_eq!(match_pattern("Σa", r"^[\p{Greek}\d]a"), Ok(true));
+        assert_eq!(match_pattern("Σa", r"^[^\p{Lu}]"), Ok(false));
+        assert_eq!(match_pattern("σ", r"(?i)\p{Lu}"), Ok(true));
+        assert_eq!(match_pattern("σ", r"(?i)\P{Lu}"), Ok(false));
+        assert_eq!(
+            match_pattern("a", r"\p{Letters}"),
+            Err(PatternError::UnknownUnicodeProperty { column: 1 })
+        );
     }
 
     #[test]
     fn test_match_pattern_start_of_string() {
-        assert_eq!(match_pattern("hello world", "^abc"), false);
-        assert_eq!(match_pattern("abcde", "^abc"), true);
-        assert_eq!(match_pattern("ade", "^abc"), false);
-        assert_eq!(match_pattern("hello world", "^hello"), true);
-        assert_eq!(match_pattern("hello world", "^Hello"), false);
-        assert_eq!(match_pattern("hello world", "^world"), false);
-        assert_eq!(match_pattern("log", "^log"), true);
-        assert_eq!(match_pattern("slog", "^alog"), false);
-        assert_eq!(match_pattern("slog", "^log"), false);
-        assert_eq!(match_pattern("log", "^(log)"), true);
-        assert_eq!(match_pattern("one log", "^(log)"), false);
-        assert_eq!(match_pattern("log", "^(\\w+)"), true);
-        assert_eq!(match_pattern("log", "^[abl]og"), true);
-        assert_eq!(match_pattern("log", "^([gol]+)"), true);
-        assert_eq!(match_pattern("log", "^([abc]+)"), false);
+        assert_eq!(match_pattern("hello world", "^abc"), Ok(false));
+        assert_eq!(match_pattern("abcde", "^abc"), Ok(true));
+        assert_eq!(match_pattern("ade", "^abc"), Ok(false));
+        assert_eq!(match_pattern("hello world", "^hello"), Ok(true));
+        assert_eq!(match_pattern("hello world", "^Hello"), Ok(false));
+        assert_eq!(match_pattern("hello world", "^world"), Ok(false));
+        assert_eq!(match_pattern("log", "^log"), Ok(true));
+        assert_eq!(match_pattern("slog", "^alog"), Ok(false));
+        assert_eq!(match_pattern("slog", "^log"), Ok(false));
+        assert_eq!(match_pattern("log", "^(log)"), Ok(true));
+        assert_eq!(match_pattern("one log", "^(log)"), Ok(false));
+        assert_eq!(match_pattern("log", "^(\\w+)"), Ok(true));
+        assert_eq!(match_pattern("log", "^[abl]og"), Ok(true));
+        assert_eq!(match_pattern("log", "^([gol]+)"), Ok(true));
+        assert_eq!(match_pattern("log", "^([abc]+)"), Ok(false));
     }
 
     #[test]
     fn test_match_pattern_end_of_string() {
-        assert_eq!(match_pattern("log", "log$"), true);
-        assert_eq!(match_pattern("log two", "log$"), false);
-        assert_eq!(match_pattern("slog", ".log$"), true);
-        assert_eq!(match_pattern("logs", "log$"), false);
-        assert_eq!(match_pattern("logs", "(log)s$"), true);
-        assert_eq!(match_pattern("log", "([gol]+)$"), true);
-        assert_eq!(match_pattern("logs", "([gol]+)$"), false);
-        assert_eq!(match_pattern("logs", "([gol]+)s$"), true);
-        assert_eq!(match_pattern("logs", "(\\w+)$"), true);
-        assert_eq!(match_pattern("logs", "(\\w+) $"), false);
+        assert_eq!(match_pattern("log", "log$"), Ok(true));
+        assert_eq!(match_pattern("log two", "log$"), Ok(false));
+        assert_eq!(match_pattern("slog", ".log$"), Ok(true));
+        assert_eq!(match_pattern("logs", "log$"), Ok(false));
+        assert_eq!(match_pattern("logs", "(log)s$"), Ok(true));
+        assert_eq!(match_pattern("log", "([gol]+)$"), Ok(true));
+        assert_eq!(match_pattern("logs", "([gol]+)$"), Ok(false));
+        assert_eq!(match_pattern("logs", "([gol]+)s$"), Ok(true));
+        assert_eq!(match_pattern("logs", "(\\w+)$"), Ok(true));
+        assert_eq!(match_pattern("logs", "(\\w+) $"), Ok(false));
+    }
+
+    #[test]
+    fn test_match_pattern_anchors_anywhere() {
+        assert_eq!(match_pattern("ab", "(^a|b$)"), Ok(true));
+        assert_eq!(match_pattern("b", "(^a|b$)"), Ok(true));
+        assert_eq!(match_pattern("ba", "(^a|b$)"), Ok(false));
+        assert_eq!(match_pattern("bar", "^(foo|bar)$"), Ok(true));
+        assert_eq!(match_pattern("bars", "^(foo|bar)$"), Ok(false));
+        assert_eq!(match_pattern("log", "lo$g"), Ok(false));
+        assert_eq!(match_pattern("a", "a^"), Ok(false));
+        assert_eq!(match_pattern("", "^$^$"), Ok(true));
+        assert_eq!(match_pattern("cat\ndog", "(?m)\\Adog"), Ok(false));
+        assert_eq!(match_pattern("cat\ndog", "(?m)\\Acat"), Ok(true));
+        assert_eq!(match_pattern("cat\ndog", "(?m)cat\\z"), Ok(false));
+        assert_eq!(match_pattern("cat\n", "cat\\Z"), Ok(true));
+        assert_eq!(match_pattern("cat\n", "cat\\z"), Ok(false));
+        assert_eq!(match_pattern("cat\ndog", "cat\\Z"), Ok(false));
+        assert_eq!(match_pattern("ab", "(?<=^)a"), Ok(true));
+        assert_eq!(match_pattern("ab", "(?<!^)a"), Ok(false));
+        assert_eq!(match_pattern("ba", "(?<!^)a"), Ok(true));
     }
 
     #[test]
     fn test_match_pattern_zero_or_one() {
-        assert_eq!(match_pattern("log", "log?"), true);
-        assert_eq!(match_pattern("loggg", "log?"), false);
-        assert_eq!(match_pattern("logs", "logs?"), true);
-        assert_eq!(match_pattern("logs", "log?s"), true);
-        assert_eq!(match_pattern("logggs", "log?s"), false);
-        assert_eq!(match_pattern("logs", "a?"), true);
-        assert_eq!(match_pattern("los", "log?"), true);
-        // assert_eq!(match_pattern("log", "a?og"), true);
+        assert_eq!(match_pattern("log", "log?"), Ok(true));
+        assert_eq!(match_pattern("loggg", "log?"), Ok(true));
+        assert_eq!(match_pattern("logs", "logs?"), Ok(true));
+        assert_eq!(match_pattern("logs", "log?s"), Ok(true));
+        assert_eq!(match_pattern("logggs", "log?s"), Ok(false));
+        assert_eq!(match_pattern("logs", "a?"), Ok(true));
+        assert_eq!(match_pattern("los", "log?"), Ok(true));
+        assert_eq!(match_pattern("log", "a?og"), Ok(true));
+        assert_eq!(find("loggg", "log?"), Ok(Some(Match { start: 0, end: 3 })));
     }
 
     #[test]
     fn test_match_pattern_one_or_more() {
-        assert_eq!(match_pattern("log", "log+"), true);
-        assert_eq!(match_pattern("loggg", "log+"), true);
-        assert_eq!(match_pattern("logs", "log+s"), true);
-        assert_eq!(match_pattern("logggs", "log+s"), true);
-        assert_eq!(match_pattern("logs", "a+"), false);
-        assert_eq!(match_pattern("los", "log+"), false);
-        assert_eq!(match_pattern("log", "a+og"), false);
+        assert_eq!(match_pattern("log", "log+"), Ok(true));
+        assert_eq!(match_pattern("loggg", "log+"), Ok(true));
+        assert_eq!(match_pattern("logs", "log+s"), Ok(true));
+        assert_eq!(match_pattern("logggs", "log+s"), Ok(true));
+        assert_eq!(match_pattern("logs", "a+"), Ok(false));
+        assert_eq!(match_pattern("los", "log+"), Ok(false));
+        assert_eq!(match_pattern("log", "a+og"), Ok(false));
+    }
+
+    #[test]
+    fn test_match_pattern_zero_or_more() {
+        assert_eq!(match_pattern("ac", "ab*c"), Ok(true));
+        assert_eq!(match_pattern("abbbc", "ab*c"), Ok(true));
+        assert_eq!(match_pattern("adc", "ab*c"), Ok(false));
+        assert_eq!(match_pattern("", "a*"), Ok(true));
+        assert_eq!(match_pattern("lg", "lo*g"), Ok(true));
+        assert_eq!(match_pattern("ababc", "(ab)*c"), Ok(true));
+        assert_eq!(match_pattern("abbac", "^[ab]*c$"), Ok(true));
+        assert_eq!(match_pattern("abdac", "^[ab]*c$"), Ok(false));
+        assert_eq!(match_pattern("aab", "(a*)*b"), Ok(true));
+        assert_eq!(find("xyz", "a*"), Ok(Some(Match { start: 0, end: 0 })));
+        assert_eq!(
+            find("12- 345-", r"\d*-"),
+            Ok(Some(Match { start: 0, end: 3 }))
+        );
+        assert_eq!(find("logs", "log.*"), Ok(Some(Match { start: 0, end: 4 })));
+        assert_eq!(
+            find_iter("a aa aaa", "a*")
+                .unwrap()
+                .filter(|m| m.start < m.end)
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..1, 2..4, 5..8]
+        );
+    }
+
+    #[test]
+    fn test_match_pattern_repeat() {
+        assert_eq!(match_pattern("555-1234", r"\d{3}-\d{4}"), Ok(true));
+        assert_eq!(match_pattern("55-1234", r"\d{3}-\d{4}"), Ok(false));
+        assert_eq!(match_pattern("call 555-1234 now", r"\d{3}-\d{4}"), Ok(true));
+        assert_eq!(find("aaaaa", "a{2}"), Ok(Some(Match { start: 0, end: 2 })));
+        assert_eq!(find("aaaaa", "a{2,}"), Ok(Some(Match { start: 0, end: 5 })));
+        assert_eq!(
+            find("aaaaa", "a{2,3}"),
+            Ok(Some(Match { start: 0, end: 3 }))
+        );
+        assert_eq!(match_pattern("a", "a{2,3}"), Ok(false));
+        assert_eq!(match_pattern("xy", "xa{0}y"), Ok(true));
+        assert_eq!(match_pattern("xay", "xa{0}y"), Ok(false));
+        assert_eq!(match_pattern("abab!", "(ab){2}!"), Ok(true));
+        assert_eq!(match_pattern("ab!", "(ab){2}!"), Ok(false));
+        assert_eq!(match_pattern("a{x}", "a{x}"), Ok(true));
+        assert_eq!(match_pattern("a{2", "a{2"), Ok(true));
+        assert_eq!(match_pattern("", "(a?){3}"), Ok(true));
+    }
+
+    #[test]
+    fn test_match_pattern_backtracking() {
+        assert_eq!(match_pattern("aaab", "a+ab"), Ok(true));
+        assert_eq!(match_pattern("dogs", r"\w+s"), Ok(true));
+        assert_eq!(match_pattern("abc", "a?abc"), Ok(true));
+        assert_eq!(match_pattern("aaa", "^a{1,3}a$"), Ok(true));
+        assert_eq!(match_pattern("aaaa", "^a{1,2}a$"), Ok(false));
+        assert_eq!(match_pattern("axxb", "a.*b"), Ok(true));
+        assert_eq!(match_pattern("axxc", "a.*b"), Ok(false));
+        assert_eq!(match_pattern("abc", "(a|ab)c"), Ok(true));
+        assert_eq!(match_pattern("abab", "^(ab)*ab$"), Ok(true));
+        assert_eq!(match_pattern("ababa", "^(ab|a)+ba$"), Ok(true));
+        assert_eq!(match_pattern("caaat", "c(a|aa)+t"), Ok(true));
+        assert_eq!(match_pattern("xyz 123 end", r"\w+ \d+ \w+d$"), Ok(true));
+        assert_eq!(match_pattern("héé", "^h.*é$"), Ok(true));
+        // The repetitions needed to reach the minimum are tried after one matching no chars
+        assert_eq!(match_pattern("ca", r"(\b|c){2}a"), Ok(true));
+        assert_eq!(match_pattern("ca", r"(\b|c){2}a(?=)"), Ok(true));
+        assert_eq!(match_pattern("ca", r"(\b|c){2}a\1?"), Ok(true));
+        assert_eq!(match_pattern("ab", r"(a??){2}b(?=)"), Ok(true));
+        assert_eq!(
+            find("log: one log: two", "log.*log"),
+            Ok(Some(Match { start: 0, end: 12 }))
+        );
+        assert_eq!(find("<a><b>", "<.+>"), Ok(Some(Match { start: 0, end: 6 })));
+
+        let caps = captures("aaab", "(a+)(ab)").unwrap().unwrap();
+        assert_eq!(caps.as_str(1), Some("aa"));
+        assert_eq!(caps.as_str(2), Some("ab"));
+        let caps = captures("abc", "(a|ab)(c)").unwrap().unwrap();
+        assert_eq!(caps.as_str(1), Some("ab"));
+        assert_eq!(caps.as_str(2), Some("c"));
+    }
+
+    #[test]
+    fn test_match_pattern_lazy_repeat() {
+        assert_eq!(find("aaa", "a+?"), Ok(Some(Match { start: 0, end: 1 })));
+        assert_eq!(find("aaa", "a*?"), Ok(Some(Match { start: 0, end: 0 })));
+        assert_eq!(find("aaa", "a??"), Ok(Some(Match { start: 0, end: 0 })));
+        assert_eq!(find("aaaa", "a{2,}?"), Ok(Some(Match { start: 0, end: 2 })));
+        assert_eq!(
+            find("ababab", "(ab)+?"),
+            Ok(Some(Match { start: 0, end: 2 }))
+        );
+        assert_eq!(
+            find("<a><b>", "<.+?>"),
+            Ok(Some(Match { start: 0, end: 3 }))
+        );
+        assert_eq!(find("aaab", "a+?b"), Ok(Some(Match { start: 0, end: 4 })));
+        assert_eq!(find("ab", "a??b"), Ok(Some(Match { start: 0, end: 2 })));
+        assert_eq!(match_pattern("xyz", "^x.*?z$"), Ok(true));
+        assert_eq!(match_pattern("xyz", "^x.+?y$"), Ok(false));
+
+        let caps = captures(r#"say "hi" and "bye""#, r#""(.+?)""#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(caps.as_str(1), Some("hi"));
+        let caps = captures(r#"say "hi" and "bye""#, r#""(.+)""#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(caps.as_str(1), Some(r#"hi" and "bye"#));
+        let caps = captures("key=a=b", "(.*?)=(.*)").unwrap().unwrap();
+        assert_eq!(caps.as_str(1), Some("key"));
+        assert_eq!(caps.as_str(2), Some("a=b"));
     }
 
     #[test]
     fn test_match_pattern_wildcard() {
-        assert_eq!(match_pattern("log", "l.g"), true);
-        assert_eq!(match_pattern("loggg", "log.."), true);
-        assert_eq!(match_pattern("logs", "...."), true);
-        assert_eq!(match_pattern("lo", "..."), false);
-        assert_eq!(match_pattern("lo", "...."), false);
+        assert_eq!(match_pattern("log", "l.g"), Ok(true));
+        assert_eq!(match_pattern("loggg", "log.."), Ok(true));
+        assert_eq!(match_pattern("logs", "...."), Ok(true));
+        assert_eq!(match_pattern("lo", "..."), Ok(false));
+        assert_eq!(match_pattern("lo", "...."), Ok(false));
+    }
+
+    #[test]
+    fn test_match_pattern_flags() {
+        assert_eq!(match_pattern("Hello", "(?i)hello"), Ok(true));
+        assert_eq!(match_pattern("HELLO", "(?i)h[a-z]+"), Ok(true));
+        assert_eq!(match_pattern("HELLO", "h(?i)ello"), Ok(false));
+        assert_eq!(match_pattern("Dog dog", r"(?i)(dog) \1"), Ok(true));
+        assert_eq!(match_pattern("cat\ndog", "^dog"), Ok(false));
+        assert_eq!(match_pattern("cat\ndog", "(?m)^dog"), Ok(true));
+        assert_eq!(match_pattern("cat\ndog", "(?m)cat$"), Ok(true));
+        assert_eq!(match_pattern("cat\ndog", "cat$"), Ok(false));
+        assert_eq!(match_pattern("cat\ndog", "t.d"), Ok(false));
+        assert_eq!(match_pattern("cat\ndog", "(?s)t.d"), Ok(true));
+        assert_eq!(
+            match_pattern("cat dog", "(?x) cat \\  dog # comment"),
+            Ok(true)
+        );
+        assert_eq!(match_pattern("catdog", "(?x) cat dog"), Ok(true));
+    }
+
+    #[test]
+    fn test_match_pattern_unicode_case_folding() {
+        assert_eq!(match_pattern("STRASSE", "(?i)straße"), Ok(false));
+        assert_eq!(match_pattern("STRAẞE", "(?i)straße"), Ok(true));
+        assert_eq!(match_pattern("GRÜN", "(?i)grün"), Ok(true));
+        assert_eq!(match_pattern("ÄÖÜ", "(?i)^[äöü]+$"), Ok(true));
+        assert_eq!(match_pattern("ΟΔΟΣ", "(?i)οδος"), Ok(true));
+        assert_eq!(match_pattern("ΟΔΟΣ", "(?i)οδός"), Ok(false));
+        assert_eq!(match_pattern("οδος", "(?i)ΟΔΟς"), Ok(true));
+        assert_eq!(match_pattern("Σσς", "(?i)^[σ]{3}$"), Ok(true));
+        assert_eq!(match_pattern("Σσς", "(?i)^[^ς]"), Ok(false));
+        // The Turkish dotless and dotted i are other letters than "i"
+        assert_eq!(match_pattern("DİYARBAKIR", "(?i)diyarbakir"), Ok(false));
+        assert_eq!(match_pattern("ıI", "(?i)^ıi$"), Ok(true));
+        assert_eq!(match_pattern("ıI", "(?i)ı{2}"), Ok(false));
+        assert_eq!(match_pattern("\u{212a}elvin", "(?i)kelvin"), Ok(true));
+        // The backreferences compare the folded chars too
+        assert_eq!(match_pattern("straße STRAẞE", r"(?i)(\w+) \1"), Ok(true));
+        assert_eq!(match_pattern("Σοφία ΣΟΦΊΑ", r"(?i)^(\w+) \1$"), Ok(true));
+        assert_eq!(match_pattern("ıi", r"(?i)(\w)\1"), Ok(false));
     }
 
     #[test]
     fn test_match_pattern_capturing_group() {
-        assert_eq!(match_pattern("fish", "(dog)"), false);
-        assert_eq!(match_pattern("dog", "(dog)"), true);
-        assert_eq!(match_pattern("doggo", "(dog)"), true);
-        assert_eq!(match_pattern("cat", "(cat)"), true);
-        assert_eq!(match_pattern("fish", "(f..h)"), true);
-        assert_eq!(match_pattern("fish", "(..s?\\w)"), true);
+        assert_eq!(match_pattern("fish", "(dog)"), Ok(false));
+        assert_eq!(match_pattern("dog", "(dog)"), Ok(true));
+        assert_eq!(match_pattern("doggo", "(dog)"), Ok(true));
+        assert_eq!(match_pattern("cat", "(cat)"), Ok(true));
+        assert_eq!(match_pattern("fish", "(f..h)"), Ok(true));
+        assert_eq!(match_pattern("fish", "(..s?\\w)"), Ok(true));
     }
 
     #[test]
     fn test_match_pattern_alternation() {
-        assert_eq!(match_pattern("fish", "(dog|cat)"), false);
-        assert_eq!(match_pattern("dog", "(dog|cat)"), true);
-        assert_eq!(match_pattern("doggo", "(dog|cat)"), true);
-        assert_eq!(match_pattern("cat", "(dog|cat)"), true);
-        assert_eq!(match_pattern("fish", "(dog|cat|f..h)"), true);
-        assert_eq!(match_pattern("fish", "(dog|..s?\\w)"), true);
+        assert_eq!(match_pattern("fish", "(dog|cat)"), Ok(false));
+        assert_eq!(match_pattern("dog", "(dog|cat)"), Ok(true));
+        assert_eq!(match_pattern("doggo", "(dog|cat)"), Ok(true));
+        assert_eq!(match_pattern("cat", "(dog|cat)"), Ok(true));
+        assert_eq!(match_pattern("fish", "(dog|cat|f..h)"), Ok(true));
+        assert_eq!(match_pattern("fish", "(dog|..s?\\w)"), Ok(true));
+        assert_eq!(match_pattern("cat", "dog|cat"), Ok(true));
+        assert_eq!(match_pattern("cow", "dog|cat"), Ok(false));
+        assert_eq!(match_pattern("acbc", "^((a|b)c)+$"), Ok(true));
+        assert_eq!(match_pattern("acbd", "^((a|b)c)+$"), Ok(false));
+        assert_eq!(match_pattern("a|b", "[|]"), Ok(true));
+        assert_eq!(match_pattern("ab", "[|]"), Ok(false));
+        assert_eq!(match_pattern("xa", "^a|b$"), Ok(false));
+        assert_eq!(match_pattern("xb", "^a|b$"), Ok(true));
     }
 
     #[test]
     fn test_match_pattern_backreference() {
-        assert_eq!(match_pattern("fish", "\\1"), false);
-        assert_eq!(match_pattern("fish fish", "(fish) \\1"), true);
-        assert_eq!(match_pattern("f f", "(f) \\1"), true);
-        assert_eq!(match_pattern("dog", "(dog|cat)"), true);
-        assert_eq!(match_pattern("doggo", "(dog|cat)"), true);
-        assert_eq!(match_pattern("cat", "(dog|cat)"), true);
-        assert_eq!(match_pattern("fish", "(dog|cat|f..h)"), true);
-        assert_eq!(match_pattern("fish", "(dog|..s?\\w)"), true);
-        assert_eq!(match_pattern("cat and dog", "(cat) and \\1"), false);
+        assert_eq!(
+            match_pattern("fish", "\\1"),
+            Err(PatternError::InvalidBackreference { column: 1 })
+        );
+        assert_eq!(match_pattern("fish fish", "(fish) \\1"), Ok(true));
+        assert_eq!(match_pattern("f f", "(f) \\1"), Ok(true));
+        assert_eq!(match_pattern("dog", "(dog|cat)"), Ok(true));
+        assert_eq!(match_pattern("doggo", "(dog|cat)"), Ok(true));
+        assert_eq!(match_pattern("cat", "(dog|cat)"), Ok(true));
+        assert_eq!(match_pattern("fish", "(dog|cat|f..h)"), Ok(true));
+        assert_eq!(match_pattern("fish", "(dog|..s?\\w)"), Ok(true));
+        assert_eq!(match_pattern("cat and dog", "(cat) and \\1"), Ok(false));
         assert_eq!(
             match_pattern(
                 "grep 101 is doing grep 101 times",
                 "(\\w\\w\\w\\w \\d\\d\\d) is doing \\1 times"
             ),
-            true
+            Ok(true)
         );
         assert_eq!(
             match_pattern("abcd is abcd, not efg", "([abcd]+) is \\1, not [^xyz]+"),
-            true
+            Ok(true)
+        );
+        assert_eq!(match_pattern("abcd is abcd", "([abcd]+) is \\1"), Ok(true));
+        assert_eq!(match_pattern("abab", r"(?<x>a|b)\k<x>"), Ok(false));
+        assert_eq!(match_pattern("abba", r"(?P<x>a|b)\k<x>"), Ok(true));
+        assert_eq!(match_pattern("a-b-a", r"(?:(a)-)(b)-\1"), Ok(true));
+        // The reference matches the text that the group captured, not its pattern
+        assert_eq!(match_pattern("cat dog", r"(\w+) \1"), Ok(false));
+        assert_eq!(match_pattern("the the", r"\b(\w+) \1\b"), Ok(true));
+        assert_eq!(match_pattern("abab ba", r"^((a|b)+) \2\1$"), Ok(false));
+        assert_eq!(match_pattern("abab babab", r"^((a|b)+) \2\1$"), Ok(true));
+        assert_eq!(match_pattern("a", r"(x)?a\1"), Ok(false));
+        assert_eq!(match_pattern("aa", r"(a)\10"), Ok(false));
+        assert_eq!(match_pattern("aa0", r"(a)\10"), Ok(true));
+        assert_eq!(
+            match_pattern("abcdefghijj", r"(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\10"),
+            Ok(true)
+        );
+        assert_eq!(
+            match_pattern("abcdefghija0", r"(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\10"),
+            Ok(false)
         );
-        assert_eq!(match_pattern("abcd is abcd", "([abcd]+) is \\1"), true);
+        assert_eq!(
+            match_pattern("abcdefghija0", r"(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\1\0"),
+            Ok(true)
+        );
+        // The repetitions are backtracked without recursing, however many there are on the line
+        assert_eq!(match_pattern(&"ab".repeat(10_000), r"^(ab)+\1$"), Ok(true));
+        assert_eq!(
+            match_pattern(&"word ".repeat(10_000), r"(\w+ )+\1"),
+            Ok(true)
+        );
+        assert_eq!(match_pattern(&"ab".repeat(10_000), r"^(ab)+\1c"), Ok(false));
         // echo "$?! 101 is doing $?! 101 times" | ./your_grep.sh -E "(\w\w\w \d\d\d) is doing \1 times"
     }
+
+    #[test]
+    fn test_match_pattern_lookarounds() {
+        assert_eq!(match_pattern("price: -42", r"(?<!-)\b\d+"), Ok(false));
+        assert_eq!(match_pattern("price: 42", r"(?<!-)\b\d+"), Ok(true));
+        assert_eq!(match_pattern("foobar", "foo(?=bar)"), Ok(true));
+        assert_eq!(match_pattern("foobaz", "foo(?=bar)"), Ok(false));
+        assert_eq!(match_pattern("foobaz", "foo(?!bar)"), Ok(true));
+        assert_eq!(match_pattern("foobar", "(?<=foo)bar"), Ok(true));
+        assert_eq!(match_pattern("foobar", "(?<=fo{1,3})bar"), Ok(true));
+        assert_eq!(match_pattern("foooobar", "(?<=fo{1,3}b)ar"), Ok(false));
+        assert_eq!(
+            match_pattern("fobar", "(?<=fo+)bar"),
+            Err(PatternError::UnboundedLookbehind { column: 1 })
+        );
+        assert_eq!(match_pattern("fobar", "(?<=x|fo)bar"), Ok(true));
+        assert_eq!(match_pattern("éb", "(?<=é)b"), Ok(true));
+        assert_eq!(match_pattern("ab", "(?<!a)b"), Ok(false));
+        assert_eq!(match_pattern("b", "(?<!a)b"), Ok(true));
+        assert_eq!(
+            match_pattern("password1", "^(?=.*\\d)(?=.*[a-z]).{8,}$"),
+            Ok(true)
+        );
+        assert_eq!(
+            match_pattern("password", "^(?=.*\\d)(?=.*[a-z]).{8,}$"),
+            Ok(false)
+        );
+        // A lookbehind only looks back as far as it can match, so a long line is checked in
+        // linear time
+        assert_eq!(match_pattern(&"a-".repeat(20_000), r"(?<!-)\d"), Ok(false));
+        assert_eq!(
+            match_pattern(&"a".repeat(20_000), r"(?<=b{0,3}|c)a"),
+            Ok(true)
+        );
+        // Without backreferences, each instruction is only taken once at each position
+        assert_eq!(
+            match_pattern(&"a".repeat(5_000), r"(?=a)(a|aa)*[bc]"),
+            Ok(false)
+        );
+        assert_eq!(
+            match_pattern(&format!("{}c", "a".repeat(5_000)), r"(?=a)(a|aa)*[bc]"),
+            Ok(true)
+        );
+        assert_eq!(
+            match_pattern(&"ab".repeat(2_000), r"(?<=(a|ab){0,3})(?!(ab|a)*c)x"),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(
+            find("hello world", "w"),
+            Ok(Some(Match { start: 6, end: 7 }))
+        );
+        assert_eq!(find("hello world", "z"), Ok(None));
+        assert_eq!(
+            find("hello world", "o"),
+            Ok(Some(Match { start: 4, end: 5 }))
+        );
+        assert_eq!(
+            find("sally has 3 apples", r"\d apple"),
+            Ok(Some(Match { start: 10, end: 17 }))
+        );
+        assert_eq!(find("logs", "log$"), Ok(None));
+        assert_eq!(find("slog", "log$"), Ok(Some(Match { start: 1, end: 4 })));
+        assert_eq!(
+            find("log log", "^log"),
+            Ok(Some(Match { start: 0, end: 3 }))
+        );
+        assert_eq!(find("loggg", "log+"), Ok(Some(Match { start: 0, end: 5 })));
+        assert_eq!(
+            find("a dog", "(dog|cat)"),
+            Ok(Some(Match { start: 2, end: 5 }))
+        );
+        assert_eq!(
+            find("héllo wörld", "w.r"),
+            Ok(Some(Match { start: 7, end: 11 }))
+        );
+        assert_eq!(
+            find("héllo wörld", "w.r")
+                .unwrap()
+                .unwrap()
+                .as_str("héllo wörld"),
+            "wör"
+        );
+    }
+
+    #[test]
+    fn test_find_iter() {
+        assert_eq!(
+            find_iter("a1b22c333", r"\d+").unwrap().collect::<Vec<_>>(),
+            vec![
+                Match { start: 1, end: 2 },
+                Match { start: 3, end: 5 },
+                Match { start: 6, end: 9 }
+            ]
+        );
+        assert_eq!(
+            find_iter("cat dog cow", "(dog|cat)")
+                .unwrap()
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..3, 4..7]
+        );
+        assert_eq!(find_iter("hello", r"\d").unwrap().count(), 0);
+        assert_eq!(find_iter("log log log", "^log").unwrap().count(), 1);
+        assert_eq!(find_iter("log log", "\\Alog").unwrap().count(), 1);
+        assert_eq!(find_iter("a\nb\n", "(?m)^\\w").unwrap().count(), 2);
+        assert_eq!(find_iter("ab", "x?").unwrap().count(), 3);
+        assert_eq!(
+            find_iter("dé", "x?")
+                .unwrap()
+                .map(|m| m.start)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 3]
+        );
+    }
+
+    #[test]
+    fn test_captures() {
+        assert_eq!(captures("hello", r"\d"), Ok(None));
+
+        let caps = captures("user=admin id=42", r"user=(\w+) id=(\d+)")
+            .unwrap()
+            .unwrap();
+        assert_eq!(caps.len(), 3);
+        assert_eq!(caps.as_str(0), Some("user=admin id=42"));
+        assert_eq!(caps.as_str(1), Some("admin"));
+        assert_eq!(caps.as_str(2), Some("42"));
+        assert_eq!(caps.get(2), Some(Match { start: 14, end: 16 }));
+        assert_eq!(caps.get(3), None);
+
+        let caps = captures("a cat and a dog", "(dog|cat) and a (dog|cat)")
+            .unwrap()
+            .unwrap();
+        assert_eq!(caps.get(0), Some(Match { start: 2, end: 15 }));
+        assert_eq!(caps.as_str(1), Some("cat"));
+        assert_eq!(caps.as_str(2), Some("dog"));
+
+        let caps = captures("log", "^(log)").unwrap().unwrap();
+        assert_eq!(caps.as_str(1), Some("log"));
+
+        let caps = captures("xy", "(a)?xy").unwrap().unwrap();
+        assert_eq!(caps.len(), 2);
+        assert_eq!(caps.get(1), None);
+
+        // The repetitions match the same with the backtracker
+        for pattern_string in [r"b*\s(c??)+", r"b*\s(c??)+(?=)"] {
+            let caps = captures(" c", pattern_string).unwrap().unwrap();
+            assert_eq!(caps.get(0), Some(Match { start: 0, end: 2 }));
+            assert_eq!(caps.as_str(1), Some("c"));
+        }
+
+        let caps = captures("fish fish", "(fish) \\1").unwrap().unwrap();
+        assert_eq!(caps.as_str(0), Some("fish fish"));
+        assert_eq!(caps.as_str(1), Some("fish"));
+
+        // The groups are numbered by the position of their "("
+        let caps = captures("abcd", "((a)(b(c)))(d)").unwrap().unwrap();
+        assert_eq!(caps.len(), 6);
+        assert_eq!(
+            (1..6).map(|index| caps.as_str(index)).collect::<Vec<_>>(),
+            vec![Some("abc"), Some("a"), Some("bc"), Some("c"), Some("d")]
+        );
+        let caps = captures("xbc", "(a)|(b)(c)").unwrap().unwrap();
+        assert_eq!(caps.get(1), None);
+        assert_eq!(caps.as_str(2), Some("b"));
+        assert_eq!(caps.as_str(3), Some("c"));
+
+        // The non-capturing groups don't shift the numbers, and the named ones can be looked up
+        let caps = captures("2024-06-01", r"(?:(\d+)-)+(?<day>\d+)")
+            .unwrap()
+            .unwrap();
+        assert_eq!(caps.len(), 3);
+        assert_eq!(caps.as_str(1), Some("06"));
+        assert_eq!(caps.as_str(2), Some("01"));
+        assert_eq!(caps.name("day"), Some(Match { start: 8, end: 10 }));
+        assert_eq!(caps.name_as_str("day"), Some("01"));
+        assert_eq!(caps.name("month"), None);
+
+        // A positive lookaround keeps what it captured, without being part of the match
+        let caps = captures("$42", r"(?<=(\$))\d+").unwrap().unwrap();
+        assert_eq!(caps.get(0), Some(Match { start: 1, end: 3 }));
+        assert_eq!(caps.as_str(1), Some("$"));
+        let caps = captures("ab", r"a(?!(c))").unwrap().unwrap();
+        assert_eq!(caps.get(1), None);
+
+        let caps = captures("héllo wörld", r"(\w+) (w)").unwrap().unwrap();
+        assert_eq!(
+            caps.iter().collect::<Vec<_>>(),
+            vec![
+                Some(Match { start: 0, end: 8 }),
+                Some(Match { start: 0, end: 6 }),
+                Some(Match { start: 7, end: 8 })
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert_eq!(
+            match_pattern("log", "(log"),
+            Err(PatternError::UnclosedCapturingGroup { column: 1 })
+        );
+        assert_eq!(find("log", "lo$g"), Ok(None));
+        assert!(find_iter("log", "[log").is_err());
+        assert!(captures("log", "+log").is_err());
+        // Matching it in linear time would take too much memory
+        assert_eq!(
+            match_pattern("caaa", "(?:a|aa){500000}c"),
+            Err(PatternError::PatternTooLarge)
+        );
+        assert_eq!(
+            Regex::new("((a{1000}){1000})").map(|_| ()),
+            Err(PatternError::PatternTooLarge)
+        );
+        assert_eq!(match_pattern("aaa", "(a{10}){10}"), Ok(false));
+        assert_eq!(match_pattern("xaay", "x.{0,5000}y"), Ok(true));
+        assert_eq!(match_pattern("aaa", "a{10000}"), Ok(false));
+        assert_eq!(match_pattern("abc", r"\w{9999}"), Ok(false));
+    }
+
+    #[test]
+    fn test_regex() {
+        let regex = Regex::new(r"ERROR \d+").unwrap();
+        assert_eq!(regex.is_match("12:00 ERROR 500 internal"), true);
+        assert_eq!(regex.is_match("12:00 INFO 200 ok"), false);
+        assert_eq!(
+            regex.find("12:00 ERROR 500 internal"),
+            Some(Match { start: 6, end: 15 })
+        );
+        assert_eq!(
+            regex
+                .find_iter("ERROR 1, ERROR 22")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..7, 9..17]
+        );
+        assert_eq!(regex.captures("INFO 200"), None);
+        assert_eq!(
+            Regex::new("(a|b"),
+            Err(PatternError::UnclosedCapturingGroup { column: 1 })
+        );
+    }
+    #[test]
+    fn test_regex_with_flags() {
+        let find_ranges = |pattern: &str, flags: Flags, input: &str| {
+            Regex::with_flags(pattern, Mode::Unicode, flags)
+                .unwrap()
+                .find_iter(input)
+                .map(|m| m.range())
+                .collect::<Vec<_>>()
+        };
+        let case_insensitive = Flags {
+            is_case_insensitive: true,
+            ..Flags::default()
+        };
+        assert_eq!(
+            find_ranges("log", case_insensitive, "Log LOG"),
+            vec![0..3, 4..7]
+        );
+        let multiline = Flags {
+            is_multiline: true,
+            ..Flags::default()
+        };
+        assert_eq!(find_ranges("^$", multiline, "a\n\nb\n"), vec![2..2, 5..5]);
+        assert_eq!(
+            find_ranges("^a|b$", multiline, "a\nb\nab"),
+            vec![0..1, 2..3, 4..5, 5..6]
+        );
+        // The flags set inline are only for the rest of the group
+        assert_eq!(
+            find_ranges("(?-i)a(?i:b)", case_insensitive, "aB Ab"),
+            vec![0..2]
+        );
+    }
+
+    #[test]
+    fn test_regex_with_invalid_utf8() {
+        let regex = Regex::new("w(.)r").unwrap();
+        assert_eq!(regex.is_match(b"w\xffr"), false);
+        assert_eq!(regex.is_match(b"\xffw\xc3\xb6r\xff"), true);
+        assert_eq!(
+            regex.find(b"\xffw\xc3\xb6r\xff"),
+            Some(Match { start: 1, end: 5 })
+        );
+        let caps = regex.captures(&b"w\xc3\xb6r"[..]).unwrap();
+        assert_eq!(caps.as_bytes(1), Some(&b"\xc3\xb6"[..]));
+        assert_eq!(caps.as_str(1), Some("ö"));
+        assert_eq!(
+            Regex::new("x*")
+                .unwrap()
+                .find_iter(b"\xffx\xc3\xb6")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..0, 1..2, 2..2, 4..4]
+        );
+    }
+
+    #[test]
+    fn test_regex_with_bytes_mode() {
+        let regex = Regex::with_mode("w(.)r", Mode::Bytes).unwrap();
+        assert_eq!(regex.is_match(b"w\xffr"), true);
+        assert_eq!(regex.is_match("wör"), false);
+        let caps = regex.captures(b"w\xffr").unwrap();
+        assert_eq!(caps.as_bytes(1), Some(&b"\xff"[..]));
+        assert_eq!(caps.as_str(1), None);
+
+        // Each byte of the pattern is a char too
+        let regex = Regex::with_mode("ö+", Mode::Bytes).unwrap();
+        assert_eq!(regex.find("wörd"), Some(Match { start: 1, end: 3 }));
+        assert_eq!(regex.is_match("w\u{f6}\u{f6}rd"), true);
+        // A quantifier only repeats the last byte of a char encoded in several bytes
+        let regex = Regex::with_mode("ö{2}", Mode::Bytes).unwrap();
+        assert_eq!(regex.is_match("öö"), false);
+        assert_eq!(
+            regex.find(b"\xc3\xb6\xb6"),
+            Some(Match { start: 0, end: 3 })
+        );
+
+        // The classes of chars only have the ASCII chars
+        let regex = Regex::with_mode(r"\bt\b", Mode::Bytes).unwrap();
+        assert_eq!(regex.is_match(b"\xe9t\xe9"), true);
+        let regex = Regex::with_mode("^[[:alpha:]]+$", Mode::Bytes).unwrap();
+        assert_eq!(regex.is_match("abc"), true);
+        assert_eq!(regex.is_match(b"\xe9t\xe9"), false);
+        let regex = Regex::with_mode(r"^\w+$", Mode::Bytes).unwrap();
+        assert_eq!(regex.is_match(b"\xe9t\xe9"), false);
+        let regex = Regex::with_mode(r"^\p{L}\P{L}$", Mode::Bytes).unwrap();
+        assert_eq!(regex.is_match(b"t\xe9"), true);
+        assert_eq!(regex.is_match(b"\xe9t"), false);
+        let regex = Regex::with_mode(r"^\w+$", Mode::Unicode).unwrap();
+        assert_eq!(regex.is_match("été"), true);
+
+        // Only the bytes mode takes a pattern that isn't valid UTF-8
+        let regex = Regex::with_mode(b"caf\xe9|\xff+", Mode::Bytes).unwrap();
+        assert_eq!(regex.find(b"un caf\xe9"), Some(Match { start: 3, end: 7 }));
+        assert_eq!(
+            regex.find(b"\xc3\xff\xff"),
+            Some(Match { start: 1, end: 3 })
+        );
+        assert_eq!(regex.is_match("café"), false);
+        assert_eq!(
+            Regex::with_mode(b"\xc3\xa9t\xe9", Mode::Unicode),
+            Err(PatternError::InvalidUtf8 { column: 3 })
+        );
+    }
 }
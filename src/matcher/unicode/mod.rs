@@ -0,0 +1,71 @@
+use self::tables::{GENERAL_CATEGORIES, SCRIPTS};
+use std::cmp::Ordering;
+
+#[allow(clippy::module_inception, clippy::bool_assert_comparison)]
+mod tests;
+
+mod tables;
+
+// The properties with their names and the ranges of their chars
+type PropertyTable = &'static [(&'static [&'static str], &'static [(char, char)])];
+
+// A general category or a script of the Unicode chars, like "L" for the letters or "Greek"
+#[derive(PartialEq, Clone, Copy)]
+pub struct Property {
+    name: &'static str,
+    ranges: &'static [(char, char)],
+}
+
+impl Property {
+    // Looks the property up by any of its names, ignoring the case, the spaces, the underscores
+    // and the hyphens like Unicode recommends. The name can be prefixed with "gc=" or "sc=",
+    // otherwise the general categories come before the scripts
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (tables, value): (&[PropertyTable], &str) = match name.split_once('=') {
+            Some((kind, value)) => match loose_name(kind).as_str() {
+                "gc" | "generalcategory" => (&[GENERAL_CATEGORIES], value),
+                "sc" | "script" => (&[SCRIPTS], value),
+                _ => return None,
+            },
+            None => (&[GENERAL_CATEGORIES, SCRIPTS], name),
+        };
+        let value = loose_name(value);
+        tables
+            .iter()
+            .flat_map(|table| table.iter())
+            .find(|(names, _)| names.iter().any(|name| loose_name(name) == value))
+            .map(|(names, ranges)| Property {
+                // The long name
+                name: names[1],
+                ranges,
+            })
+    }
+
+    pub fn contains(&self, char: char) -> bool {
+        self.ranges
+            .binary_search_by(|&(start, end)| {
+                if end < char {
+                    Ordering::Less
+                } else if start > char {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+}
+
+// The tables are left out, as the name tells the property
+impl std::fmt::Debug for Property {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.debug_tuple("Property").field(&self.name).finish()
+    }
+}
+
+fn loose_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use crate::matcher::unicode::{tables::UNICODE_VERSION, Property};
+
+    fn property(name: &str) -> Property {
+        Property::from_name(name).unwrap()
+    }
+
+    #[test]
+    fn test_property_from_name() {
+        assert_eq!(Property::from_name("L"), Property::from_name("Letter"));
+        assert_eq!(
+            Property::from_name("lu"),
+            Property::from_name("Uppercase Letter")
+        );
+        assert_eq!(Property::from_name("gc=Nd"), Property::from_name("digit"));
+        assert_eq!(Property::from_name("Greek"), Property::from_name("sc=Grek"));
+        assert_eq!(
+            Property::from_name("script=han"),
+            Property::from_name("Han")
+        );
+        assert_ne!(Property::from_name("Lu"), Property::from_name("Ll"));
+        assert_eq!(Property::from_name("Klingon"), None);
+        assert_eq!(Property::from_name("sc=Lu"), None);
+        assert_eq!(Property::from_name("block=Greek"), None);
+        assert_eq!(
+            format!("{:?}", property("Nd")),
+            "Property(\"Decimal_Number\")"
+        );
+    }
+
+    #[test]
+    fn test_property_contains() {
+        assert_eq!(property("L").contains('a'), true);
+        assert_eq!(property("L").contains('ß'), true);
+        assert_eq!(property("L").contains('1'), false);
+        assert_eq!(property("Lu").contains('Σ'), true);
+        assert_eq!(property("Lu").contains('σ'), false);
+        assert_eq!(property("Nd").contains('٣'), true);
+        assert_eq!(property("Nd").contains('½'), false);
+        assert_eq!(property("Greek").contains('λ'), true);
+        assert_eq!(property("Greek").contains('l'), false);
+        assert_eq!(property("Han").contains('漢'), true);
+        assert_eq!(property("Han").contains('か'), false);
+        assert_eq!(property("Common").contains(' '), true);
+        assert_eq!(property("Cn").contains('\u{10ffff}'), true);
+        assert_eq!(property("C").contains('\u{e000}'), true);
+        // Added in Unicode 16, that the chars of Rust already know as letters
+        assert_eq!(property("L").contains('\u{a7cb}'), true);
+        assert_eq!(property("Sidetic").contains('\u{10940}'), true);
+    }
+
+    #[test]
+    fn test_unicode_version() {
+        // The tables have to be regenerated for the Unicode version of Rust, so that the
+        // properties agree with the methods of the chars, like the ones that \w and [[:upper:]] use
+        assert_eq!(UNICODE_VERSION, char::UNICODE_VERSION);
+        let (number, control) = (property("N"), property("Cc"));
+        for char in (0..=0x10ffff).filter_map(char::from_u32) {
+            assert_eq!(number.contains(char), char.is_numeric(), "{:?}", char);
+            assert_eq!(control.contains(char), char.is_control(), "{:?}", char);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+#[cfg(test)]
+mod tests {
+    use crate::args::{BinaryFiles, Options};
+    use crate::{is_searching_several_files, is_utf8_locale, Grep};
+    use grep_starter_rust::matcher::{Flags, Mode, Regex};
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process;
+
+    // A search writing into memory, with the patterns of the options
+    fn grep(options: Options) -> Grep<Vec<u8>> {
+        let flags = Flags {
+            is_case_insensitive: options.is_case_insensitive,
+            is_multiline: options.is_multiline,
+            ..Flags::default()
+        };
+        Grep {
+            regexes: options
+                .patterns
+                .iter()
+                .map(|pattern| Regex::with_flags(pattern, Mode::Unicode, flags).unwrap())
+                .collect(),
+            mode: Mode::Unicode,
+            with_filename: options.with_filename.unwrap_or(false),
+            options,
+            writer: Vec::new(),
+            is_any_match: false,
+            is_any_error: false,
+        }
+    }
+
+    fn patterns(patterns: &[&str]) -> Vec<Vec<u8>> {
+        patterns
+            .iter()
+            .map(|pattern| pattern.as_bytes().to_vec())
+            .collect()
+    }
+
+    // The result of searching the text, with the output
+    fn search(options: Options, text: &[u8]) -> (bool, String) {
+        let mut grep = grep(options);
+        let is_match = grep.search_reader(text, "name").unwrap();
+        (is_match, String::from_utf8(grep.writer).unwrap())
+    }
+
+    // A new directory for the files of a test
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("grep-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn test_search_reader() {
+        let text = b"apple pie\nbanana\ncherry pie\n";
+        let options = Options {
+            patterns: patterns(&["pie"]),
+            ..Options::default()
+        };
+        assert_eq!(
+            search(options.clone(), text),
+            (true, "apple pie\ncherry pie\n".to_string())
+        );
+        assert_eq!(
+            search(
+                Options {
+                    with_line_number: true,
+                    with_filename: Some(true),
+                    ..options.clone()
+                },
+                text
+            ),
+            (true, "name:1:apple pie\nname:3:cherry pie\n".to_string())
+        );
+        assert_eq!(
+            search(
+                Options {
+                    is_inverted: true,
+                    ..options.clone()
+                },
+                text
+            ),
+            (true, "banana\n".to_string())
+        );
+        assert_eq!(
+            search(
+                Options {
+                    is_count: true,
+                    with_filename: Some(true),
+                    ..options.clone()
+                },
+                text
+            ),
+            (true, "name:2\n".to_string())
+        );
+        assert_eq!(
+            search(
+                Options {
+                    patterns: patterns(&["p[a-z]+", "an"]),
+                    is_only_matching: true,
+                    ..Options::default()
+                },
+                text
+            ),
+            (true, "pple\npie\nan\nan\npie\n".to_string())
+        );
+        // The last line doesn't need a newline
+        assert_eq!(
+            search(options.clone(), b"no\npie"),
+            (true, "pie\n".to_string())
+        );
+        assert_eq!(search(options, b"banana\n"), (false, String::new()));
+    }
+
+    #[test]
+    fn test_search_reader_quiet() {
+        let options = Options {
+            patterns: patterns(&["pie"]),
+            is_quiet: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            search(options.clone(), b"apple pie\ncherry pie\n"),
+            (true, String::new())
+        );
+        assert_eq!(search(options, b"banana\n"), (false, String::new()));
+    }
+
+    #[test]
+    fn test_search_reader_multiline() {
+        let text = b"one\ntwo\nthree\nfour\n";
+        let options = Options {
+            patterns: patterns(&["e\ntw|^f"]),
+            is_multiline: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            search(
+                Options {
+                    with_line_number: true,
+                    ..options.clone()
+                },
+                text
+            ),
+            (true, "1:one\n2:two\n4:four\n".to_string())
+        );
+        assert_eq!(
+            search(
+                Options {
+                    is_only_matching: true,
+                    ..options.clone()
+                },
+                text
+            ),
+            (true, "e\ntw\nf\n".to_string())
+        );
+        assert_eq!(
+            search(
+                Options {
+                    is_inverted: true,
+                    ..options
+                },
+                text
+            ),
+            (true, "three\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_search_reader_binary() {
+        let options = Options {
+            patterns: patterns(&["pie"]),
+            ..Options::default()
+        };
+        // Only whether a binary file matches is told, on the standard error
+        assert_eq!(
+            search(options.clone(), b"apple pie\n\0\n"),
+            (true, String::new())
+        );
+        assert_eq!(
+            search(options.clone(), b"banana\n\0\n"),
+            (false, String::new())
+        );
+        // A file is found to be binary by a line that can't be decoded, once it has to be printed
+        assert_eq!(
+            search(
+                options.clone(),
+                b"apple pie\nbanana \xff\ncherry \xff pie\n"
+            ),
+            (true, "apple pie\n".to_string())
+        );
+        assert_eq!(
+            search(
+                Options {
+                    binary_files: BinaryFiles::Text,
+                    ..options.clone()
+                },
+                b"apple pie\n\0 pie\n"
+            ),
+            (true, "apple pie\n\0 pie\n".to_string())
+        );
+        assert_eq!(
+            search(
+                Options {
+                    binary_files: BinaryFiles::WithoutMatch,
+                    ..options
+                },
+                b"apple pie\n\0\n"
+            ),
+            (false, String::new())
+        );
+    }
+
+    #[test]
+    fn test_search_path() {
+        let directory = test_directory("search-path");
+        fs::write(directory.join("one.txt"), "apple pie\n").unwrap();
+        fs::write(directory.join("two.txt"), "banana\n").unwrap();
+        fs::create_dir(directory.join("sub")).unwrap();
+        fs::write(directory.join("sub").join("three.txt"), "cherry pie\n").unwrap();
+        let options = Options {
+            patterns: patterns(&["pie"]),
+            ..Options::default()
+        };
+
+        let mut operands_grep = grep(Options {
+            with_filename: Some(true),
+            ..options.clone()
+        });
+        operands_grep.search_path(&directory.join("one.txt"));
+        operands_grep.search_path(&directory.join("two.txt"));
+        assert_eq!(
+            String::from_utf8(operands_grep.writer.clone()).unwrap(),
+            format!("{}:apple pie\n", directory.join("one.txt").display())
+        );
+        assert_eq!(operands_grep.exit_code(), 0);
+
+        // The files of the directories are searched in the order of their names
+        let mut recursive_grep = grep(Options {
+            is_recursive: true,
+            with_filename: Some(true),
+            ..options.clone()
+        });
+        recursive_grep.search_path(&directory);
+        assert_eq!(
+            String::from_utf8(recursive_grep.writer.clone()).unwrap(),
+            format!(
+                "{}:apple pie\n{}:cherry pie\n",
+                directory.join("one.txt").display(),
+                directory.join("sub").join("three.txt").display()
+            )
+        );
+        assert_eq!(recursive_grep.exit_code(), 0);
+
+        // A directory is only searched recursively
+        let mut directory_grep = grep(options);
+        directory_grep.search_path(&directory);
+        assert_eq!(directory_grep.writer, b"");
+        assert_eq!(directory_grep.exit_code(), 2);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_search_path_with_unreadable_file() {
+        let directory = test_directory("unreadable-file");
+        fs::write(directory.join("one.txt"), "apple pie\n").unwrap();
+        let options = Options {
+            patterns: patterns(&["pie"]),
+            ..Options::default()
+        };
+
+        // The other files are still searched after the warning
+        let mut grep_after_error = grep(options.clone());
+        grep_after_error.search_path(&directory.join("missing.txt"));
+        grep_after_error.search_path(&directory.join("one.txt"));
+        assert_eq!(grep_after_error.writer, b"apple pie\n");
+        assert_eq!(grep_after_error.exit_code(), 2);
+
+        // A quiet search succeeds as soon as a line is selected, whatever the errors
+        let mut quiet_grep = grep(Options {
+            is_quiet: true,
+            ..options
+        });
+        quiet_grep.search_path(&directory.join("missing.txt"));
+        quiet_grep.search_path(&directory.join("one.txt"));
+        assert_eq!(quiet_grep.writer, b"");
+        assert_eq!(quiet_grep.exit_code(), 0);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let mut grep = grep(Options {
+            patterns: patterns(&["pie"]),
+            ..Options::default()
+        });
+        assert_eq!(grep.exit_code(), 1);
+        let result = grep.search_reader(&b"banana\n"[..], "name");
+        grep.record(result, "name");
+        assert_eq!(grep.exit_code(), 1);
+        let result = grep.search_reader(&b"apple pie\n"[..], "name");
+        grep.record(result, "name");
+        assert_eq!(grep.exit_code(), 0);
+        grep.search_path(Path::new("missing.txt"));
+        assert_eq!(grep.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_is_searching_several_files() {
+        let file = || PathBuf::from("f.txt");
+        let directory = env::temp_dir;
+        assert_eq!(is_searching_several_files(&[file()], false), false);
+        assert_eq!(is_searching_several_files(&[file()], true), false);
+        assert_eq!(is_searching_several_files(&[], false), false);
+        assert_eq!(is_searching_several_files(&[], true), true);
+        assert_eq!(is_searching_several_files(&[directory()], false), false);
+        assert_eq!(is_searching_several_files(&[directory()], true), true);
+        assert_eq!(is_searching_several_files(&[file(), file()], false), true);
+    }
+
+    #[test]
+    fn test_is_utf8_locale() {
+        assert_eq!(is_utf8_locale("en_US.UTF-8"), true);
+        assert_eq!(is_utf8_locale("C.utf8"), true);
+        assert_eq!(is_utf8_locale("de_DE.UTF-8@euro"), true);
+        assert_eq!(is_utf8_locale("C"), false);
+        assert_eq!(is_utf8_locale("POSIX"), false);
+        assert_eq!(is_utf8_locale("en_US"), false);
+        assert_eq!(is_utf8_locale("fr_FR.ISO-8859-1"), false);
+    }
+}